@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
 use gloo_net::http::Request;
+use futures::future::LocalBoxFuture;
 
-// Use hermes backend proxy to avoid CORS issues
-const HERMES_DFLOW_ENDPOINT: &str = "https://hermes-titan-proxy.fly.dev/api/dflow/quote";
+use super::quote::{
+    expiry_from_now, min_out_from_slippage, parse_amount_str, ProviderId, Quote, QuoteProvider,
+    QuoteRequest, RouteLeg, SwapMode,
+};
+use crate::config;
+
+const DFLOW_QUOTE_TTL_MS: f64 = 20_000.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DflowQuoteResponse {
@@ -10,8 +16,47 @@ pub struct DflowQuoteResponse {
     pub out_amount: String,
     #[serde(rename = "inAmount")]
     pub in_amount: String,
+    #[serde(rename = "otherAmountThreshold", default)]
+    pub other_amount_threshold: Option<String>,
+    #[serde(rename = "priceImpactPct", default)]
+    pub price_impact_pct: Option<String>,
+    #[serde(default)]
+    pub routes: Vec<DflowRouteLeg>,
+}
+
+/// Dflow route hop. The proxy passes these through loosely, so every field
+/// is optional and missing amounts read as zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DflowRouteLeg {
+    #[serde(alias = "label")]
+    pub venue: Option<String>,
+    #[serde(rename = "marketKey", alias = "ammKey", default)]
+    pub market_key: Option<String>,
+    #[serde(rename = "inputMint", default)]
+    pub input_mint: Option<String>,
+    #[serde(rename = "outputMint", default)]
+    pub output_mint: Option<String>,
+    #[serde(rename = "inAmount", default)]
+    pub in_amount: Option<String>,
+    #[serde(rename = "outAmount", default)]
+    pub out_amount: Option<String>,
     #[serde(default)]
-    pub routes: Vec<serde_json::Value>,
+    pub percent: Option<u8>,
+}
+
+impl DflowRouteLeg {
+    pub fn to_leg(&self, req: &QuoteRequest) -> RouteLeg {
+        let amount = |v: &Option<String>| v.as_deref().and_then(|a| a.parse().ok()).unwrap_or(0);
+        RouteLeg {
+            label: self.venue.clone().unwrap_or_else(|| "Unknown".to_string()),
+            amm_key: self.market_key.clone(),
+            input_mint: self.input_mint.clone().unwrap_or_else(|| req.input_mint.clone()),
+            output_mint: self.output_mint.clone().unwrap_or_else(|| req.output_mint.clone()),
+            in_amount: amount(&self.in_amount),
+            out_amount: amount(&self.out_amount),
+            percent: self.percent.unwrap_or(100).min(100),
+        }
+    }
 }
 
 pub struct DflowClient;
@@ -26,7 +71,7 @@ impl DflowClient {
     ) -> Result<DflowQuoteResponse, String> {
         let url = format!(
             "{}?inputMint={}&outputMint={}&amount={}&slippageBps={}",
-            HERMES_DFLOW_ENDPOINT, input_mint, output_mint, amount, slippage_bps
+            config::endpoints().dflow_quote_url, input_mint, output_mint, amount, slippage_bps
         );
 
         log::info!("Fetching Dflow quote via hermes proxy");
@@ -49,4 +94,49 @@ impl DflowClient {
 
         Ok(quote)
     }
-}
\ No newline at end of file
+}
+
+impl DflowQuoteResponse {
+    pub fn to_quote(&self, req: &QuoteRequest) -> Result<Quote, String> {
+        let out_amount = parse_amount_str(&self.out_amount, "outAmount")?;
+        let min_out_amount = match &self.other_amount_threshold {
+            Some(v) => parse_amount_str(v, "otherAmountThreshold")?,
+            None => min_out_from_slippage(out_amount, req.slippage_bps),
+        };
+        let in_amount = parse_amount_str(&self.in_amount, "inAmount")?;
+        Ok(Quote {
+            provider: ProviderId::Dflow,
+            // Dflow only quotes ExactIn.
+            swap_mode: SwapMode::ExactIn,
+            input_mint: req.input_mint.clone(),
+            output_mint: req.output_mint.clone(),
+            in_amount,
+            out_amount,
+            min_out_amount,
+            max_in_amount: in_amount,
+            slippage_bps: req.slippage_bps,
+            price_impact_pct: self.price_impact_pct.as_deref().and_then(|v| v.parse().ok()),
+            platform_fee_bps: 0,
+            priority_fee_lamports: 0,
+            route: self.routes.iter().map(|leg| leg.to_leg(req)).collect(),
+            expires_at_ms: expiry_from_now(DFLOW_QUOTE_TTL_MS),
+            request_id: None,
+            transaction: None,
+        })
+    }
+}
+
+impl QuoteProvider for DflowClient {
+    fn id(&self) -> ProviderId {
+        ProviderId::Dflow
+    }
+
+    fn fetch_quote<'a>(&'a self, req: &'a QuoteRequest) -> LocalBoxFuture<'a, Result<Quote, String>> {
+        Box::pin(async move {
+            let quote =
+                Self::get_quote(&req.input_mint, &req.output_mint, req.amount, req.slippage_bps)
+                    .await?;
+            quote.to_quote(req)
+        })
+    }
+}
@@ -0,0 +1,146 @@
+// Signs and lands the transaction behind a quote, then reports what was
+// actually received.
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use solana_sdk::transaction::VersionedTransaction;
+
+use super::quote::{ProviderId, Quote};
+use super::JupiterClient;
+use crate::rpc::{
+    get_transaction, send_transaction_base64, wait_for_confirmation, TokenBalance, TransactionInfo,
+};
+use crate::token_registry::SOL_MINT;
+use crate::wallet::WalletAdapter;
+
+const CONFIRM_TIMEOUT_MS: f64 = 60_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionResult {
+    pub provider: ProviderId,
+    pub signature: String,
+    pub quoted_out: u64,
+    pub min_out: u64,
+    pub realized_out: Option<u64>,
+}
+
+impl ExecutionResult {
+    /// Realized output versus the quote in basis points; negative means the
+    /// user received less than quoted.
+    pub fn deviation_bps(&self) -> Option<i64> {
+        let realized = self.realized_out? as i128;
+        if self.quoted_out == 0 {
+            return None;
+        }
+        let quoted = self.quoted_out as i128;
+        Some(((realized - quoted) * 10_000 / quoted) as i64)
+    }
+}
+
+pub fn decode_transaction(tx_b64: &str) -> Result<VersionedTransaction, String> {
+    let bytes = B64.decode(tx_b64.as_bytes()).map_err(|e| e.to_string())?;
+    bincode::deserialize(&bytes).map_err(|e| format!("invalid transaction: {}", e))
+}
+
+/// Refuses transactions whose fee payer is not the connected wallet.
+pub fn verify_fee_payer(tx: &VersionedTransaction, taker: &str) -> Result<(), String> {
+    let payer = tx
+        .message
+        .static_account_keys()
+        .first()
+        .ok_or("transaction has no accounts")?;
+    if payer.to_string() != taker {
+        return Err(format!(
+            "transaction fee payer {} is not the connected wallet",
+            payer
+        ));
+    }
+    Ok(())
+}
+
+pub async fn execute_quote(
+    adapter: &WalletAdapter,
+    quote: &Quote,
+    taker: &str,
+    rpc_url: &str,
+) -> Result<ExecutionResult, String> {
+    let label = quote.provider.label();
+    if quote.is_expired(js_sys::Date::now()) {
+        return Err(format!("{} quote expired, refresh and try again", label));
+    }
+    let tx_b64 = quote
+        .transaction
+        .as_deref()
+        .ok_or(format!("{} quote has no transaction to execute", label))?;
+    let tx = decode_transaction(tx_b64)?;
+    verify_fee_payer(&tx, taker)?;
+
+    let signed_b64 = adapter.sign_transaction_base64(tx_b64).await?;
+
+    let (signature, realized_out) = match quote.provider {
+        ProviderId::Jupiter => {
+            let request_id = quote
+                .request_id
+                .as_deref()
+                .ok_or("Jupiter quote is missing its request id")?;
+            let result = JupiterClient::execute(&signed_b64, request_id).await?;
+            if result.status != "Success" {
+                return Err(format!(
+                    "Jupiter execute failed ({}): {}",
+                    result.code,
+                    result.error.unwrap_or_else(|| result.status.clone())
+                ));
+            }
+            let signature = result.signature.ok_or("Jupiter returned no signature")?;
+            let realized = result
+                .output_amount_result
+                .as_deref()
+                .and_then(|v| v.parse::<u64>().ok());
+            (signature, realized)
+        }
+        _ => {
+            let signature = send_transaction_base64(rpc_url, &signed_b64).await?;
+            wait_for_confirmation(rpc_url, &signature, CONFIRM_TIMEOUT_MS).await?;
+            let realized = match get_transaction(rpc_url, &signature).await {
+                Ok(info) => realized_output(&info, taker, &quote.output_mint),
+                Err(e) => {
+                    log::warn!("Could not load executed transaction {}: {}", signature, e);
+                    None
+                }
+            };
+            (signature, realized)
+        }
+    };
+
+    Ok(ExecutionResult {
+        provider: quote.provider,
+        signature,
+        quoted_out: quote.out_amount,
+        min_out: quote.min_out_amount,
+        realized_out,
+    })
+}
+
+/// Output received by `owner`, from the transaction's balance changes.
+pub fn realized_output(info: &TransactionInfo, owner: &str, output_mint: &str) -> Option<u64> {
+    let meta = info.meta.as_ref()?;
+    if output_mint == SOL_MINT {
+        let index = info
+            .transaction
+            .message
+            .account_keys
+            .iter()
+            .position(|k| k == owner)?;
+        let pre = *meta.pre_balances.get(index)?;
+        let post = *meta.post_balances.get(index)?;
+        // The taker pays the network fee, so add it back to isolate the swap.
+        return Some((post + if index == 0 { meta.fee } else { 0 }).saturating_sub(pre));
+    }
+    let balance = |list: &[TokenBalance]| {
+        list.iter()
+            .find(|b| b.mint == output_mint && b.owner.as_deref() == Some(owner))
+            .and_then(|b| b.ui_token_amount.amount.parse::<u64>().ok())
+    };
+    let post = balance(&meta.post_token_balances)?;
+    let pre = balance(&meta.pre_token_balances).unwrap_or(0);
+    Some(post.saturating_sub(pre))
+}
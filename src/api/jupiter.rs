@@ -1,8 +1,24 @@
 use serde::{Deserialize, Serialize};
-use gloo_net::http::Request;
+use gloo_net::http::{Request, RequestBuilder};
+use futures::future::LocalBoxFuture;
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 
-const JUPITER_ULTRA_API: &str = "https://api.jup.ag/ultra/v1";
-const JUPITER_API_KEY: &str = "ddbf7533-efd7-41a4-b794-59325ccbc383";
+use crate::config;
+use super::quote::{
+    expiry_from_now, parse_amount_str, ProviderId, Quote, QuoteProvider, QuoteRequest, RouteLeg,
+    SwapMode,
+};
+
+// Ultra orders must be executed shortly after they are issued.
+const JUPITER_QUOTE_TTL_MS: f64 = 30_000.0;
+// Swap budget when Jupiter sends no compute-unit limit of its own.
+const DEFAULT_SWAP_COMPUTE_UNITS: u32 = 400_000;
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
 
 // Jupiter Ultra Order Response - EXACT copy from original app
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +41,7 @@ pub struct JupiterUltraOrderResponse {
     #[serde(rename = "priceImpact")]
     pub price_impact: Option<f64>,
     #[serde(rename = "routePlan")]
-    pub route_plan: Vec<serde_json::Value>,
+    pub route_plan: Vec<JupiterRoutePlanStep>,
     #[serde(rename = "feeBps")]
     pub fee_bps: u16,
     #[serde(rename = "prioritizationFeeLamports")]
@@ -40,30 +56,206 @@ pub struct JupiterUltraOrderResponse {
     pub error_message: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterRoutePlanStep {
+    #[serde(rename = "swapInfo")]
+    pub swap_info: JupiterSwapInfo,
+    pub percent: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterSwapInfo {
+    #[serde(rename = "ammKey")]
+    pub amm_key: String,
+    pub label: Option<String>,
+    #[serde(rename = "inputMint")]
+    pub input_mint: String,
+    #[serde(rename = "outputMint")]
+    pub output_mint: String,
+    #[serde(rename = "inAmount")]
+    pub in_amount: String,
+    #[serde(rename = "outAmount")]
+    pub out_amount: String,
+}
+
+impl JupiterRoutePlanStep {
+    pub fn to_leg(&self) -> RouteLeg {
+        let info = &self.swap_info;
+        RouteLeg {
+            label: info.label.clone().unwrap_or_else(|| "Unknown".to_string()),
+            amm_key: Some(info.amm_key.clone()),
+            input_mint: info.input_mint.clone(),
+            output_mint: info.output_mint.clone(),
+            in_amount: info.in_amount.parse().unwrap_or(0),
+            out_amount: info.out_amount.parse().unwrap_or(0),
+            percent: self.percent.min(100),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterUltraExecuteResponse {
+    pub status: String,
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub code: i64,
+    pub error: Option<String>,
+    #[serde(rename = "inputAmountResult")]
+    pub input_amount_result: Option<String>,
+    #[serde(rename = "outputAmountResult")]
+    pub output_amount_result: Option<String>,
+}
+
+/// Quote from the Metis swap API. The raw body is kept because
+/// `/swap-instructions` expects it back verbatim.
+#[derive(Debug, Clone)]
+pub struct JupiterSwapQuote {
+    pub raw: serde_json::Value,
+    pub other_amount_threshold: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterInstruction {
+    #[serde(rename = "programId")]
+    pub program_id: String,
+    pub accounts: Vec<JupiterAccountMeta>,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterAccountMeta {
+    pub pubkey: String,
+    #[serde(rename = "isSigner")]
+    pub is_signer: bool,
+    #[serde(rename = "isWritable")]
+    pub is_writable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JupiterSwapInstructionsResponse {
+    #[serde(rename = "computeBudgetInstructions", default)]
+    pub compute_budget_instructions: Vec<JupiterInstruction>,
+    #[serde(rename = "setupInstructions", default)]
+    pub setup_instructions: Vec<JupiterInstruction>,
+    #[serde(rename = "swapInstruction")]
+    pub swap_instruction: JupiterInstruction,
+    #[serde(rename = "cleanupInstruction")]
+    pub cleanup_instruction: Option<JupiterInstruction>,
+    #[serde(rename = "otherInstructions", default)]
+    pub other_instructions: Vec<JupiterInstruction>,
+    #[serde(rename = "addressLookupTableAddresses", default)]
+    pub address_lookup_table_addresses: Vec<String>,
+}
+
+impl JupiterInstruction {
+    pub fn to_instruction(&self) -> Result<Instruction, String> {
+        let program_id = Pubkey::from_str(&self.program_id).map_err(|e| e.to_string())?;
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|a| {
+                let pubkey = Pubkey::from_str(&a.pubkey).map_err(|e| e.to_string())?;
+                Ok(AccountMeta {
+                    pubkey,
+                    is_signer: a.is_signer,
+                    is_writable: a.is_writable,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let data = B64.decode(self.data.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(Instruction {
+            program_id,
+            accounts,
+            data,
+        })
+    }
+}
+
+impl JupiterSwapInstructionsResponse {
+    /// Swap instructions in execution order. Jupiter's compute-budget
+    /// instructions are left out: its limit is simulated for the swap alone,
+    /// so callers size their own with `compute_budget`.
+    pub fn swap_instructions(&self) -> Result<Vec<Instruction>, String> {
+        self.other_instructions
+            .iter()
+            .chain(self.setup_instructions.iter())
+            .chain(std::iter::once(&self.swap_instruction))
+            .chain(self.cleanup_instruction.iter())
+            .map(JupiterInstruction::to_instruction)
+            .collect()
+    }
+
+    /// Compute budget for a transaction running the swap plus instructions
+    /// worth `extra_units`. Jupiter's priority fee, if any, is carried over.
+    pub fn compute_budget(&self, extra_units: u32) -> Result<Vec<Instruction>, String> {
+        let mut swap_units = None;
+        let mut unit_price = None;
+        for ix in &self.compute_budget_instructions {
+            let ix = ix.to_instruction()?;
+            if ix.program_id != compute_budget::id() {
+                continue;
+            }
+            match ix.data.split_first() {
+                Some((2, rest)) => {
+                    swap_units = rest.try_into().ok().map(u32::from_le_bytes);
+                }
+                Some((3, rest)) => {
+                    unit_price = rest.try_into().ok().map(u64::from_le_bytes);
+                }
+                _ => {}
+            }
+        }
+        let limit = swap_units
+            .unwrap_or(DEFAULT_SWAP_COMPUTE_UNITS)
+            .saturating_add(extra_units)
+            .min(MAX_COMPUTE_UNITS);
+        let mut budget = vec![ComputeBudgetInstruction::set_compute_unit_limit(limit)];
+        budget.extend(unit_price.map(ComputeBudgetInstruction::set_compute_unit_price));
+        Ok(budget)
+    }
+}
+
 pub struct JupiterClient;
 
+/// Attaches the configured API key, if the deployment has one.
+fn with_api_key(request: RequestBuilder) -> RequestBuilder {
+    match config::endpoints().jupiter_api_key {
+        Some(key) => request.header("x-api-key", &key),
+        None => request,
+    }
+}
+
 impl JupiterClient {
     /// Get Jupiter Ultra order (quote + unsigned transaction)
     pub async fn get_quote(
         input_mint: &str,
         output_mint: &str,
         amount: u64,
-        slippage_bps: u16,
+        swap_mode: SwapMode,
+        slippage_bps: Option<u16>,
         user_pubkey: Option<&str>,
     ) -> Result<JupiterUltraOrderResponse, String> {
         let mut url = format!(
-            "{}/order?inputMint={}&outputMint={}&amount={}&slippageBps={}",
-            JUPITER_ULTRA_API, input_mint, output_mint, amount, slippage_bps
+            "{}/order?inputMint={}&outputMint={}&amount={}",
+            config::endpoints().jupiter_ultra_api, input_mint, output_mint, amount
         );
 
+        if swap_mode == SwapMode::ExactOut {
+            url.push_str("&swapMode=ExactOut");
+        }
+
+        // Without slippageBps Ultra picks its own real-time slippage estimate.
+        if let Some(bps) = slippage_bps {
+            url.push_str(&format!("&slippageBps={}", bps));
+        }
+
         if let Some(pubkey) = user_pubkey {
             url.push_str(&format!("&taker={}", pubkey));
         }
 
         log::info!("Fetching Jupiter Ultra order: {}", url);
 
-        let response = Request::get(&url)
-            .header("x-api-key", JUPITER_API_KEY)
+        let response = with_api_key(Request::get(&url))
             .send()
             .await
             .map_err(|e| format!("Jupiter request failed: {:?}", e))?;
@@ -81,4 +273,227 @@ impl JupiterClient {
 
         Ok(order)
     }
-}
\ No newline at end of file
+
+    /// Get a Metis quote for composing the swap into our own transaction
+    pub async fn get_swap_quote(
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+        slippage_bps: u16,
+        max_accounts: Option<u8>,
+    ) -> Result<JupiterSwapQuote, String> {
+        let mut url = format!(
+            "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+            config::endpoints().jupiter_swap_api, input_mint, output_mint, amount, slippage_bps
+        );
+
+        // Leaves room for our own instructions in the same transaction.
+        if let Some(max) = max_accounts {
+            url.push_str(&format!("&maxAccounts={}", max));
+        }
+
+        log::info!("Fetching Jupiter swap quote: {}", url);
+
+        let response = with_api_key(Request::get(&url))
+            .send()
+            .await
+            .map_err(|e| format!("Jupiter request failed: {:?}", e))?;
+
+        if !response.ok() {
+            return Err(format!("Jupiter API error: {}", response.status()));
+        }
+
+        let raw = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to parse Jupiter quote: {:?}", e))?;
+        let field = |name: &str| {
+            raw.get(name)
+                .and_then(|v| v.as_str())
+                .ok_or(format!("Jupiter quote missing {}", name))
+                .and_then(|v| parse_amount_str(v, name))
+        };
+        Ok(JupiterSwapQuote {
+            other_amount_threshold: field("otherAmountThreshold")?,
+            raw,
+        })
+    }
+
+    /// Swap instructions for a Metis quote, to be signed by `user_pubkey`
+    pub async fn get_swap_instructions(
+        quote: &JupiterSwapQuote,
+        user_pubkey: &str,
+    ) -> Result<JupiterSwapInstructionsResponse, String> {
+        let body = serde_json::json!({
+            "quoteResponse": quote.raw,
+            "userPublicKey": user_pubkey,
+            "wrapAndUnwrapSol": true,
+            // Simulated for the swap alone; `compute_budget` adds our share.
+            "dynamicComputeUnitLimit": true,
+        });
+
+        let url = format!("{}/swap-instructions", config::endpoints().jupiter_swap_api);
+        let response = with_api_key(Request::post(&url))
+            .json(&body)
+            .map_err(|e| format!("Failed to build Jupiter swap-instructions request: {:?}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Jupiter swap-instructions failed: {:?}", e))?;
+
+        if !response.ok() {
+            return Err(format!("Jupiter API error: {}", response.status()));
+        }
+
+        response
+            .json::<JupiterSwapInstructionsResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse Jupiter swap instructions: {:?}", e))
+    }
+
+    /// Submit a signed Ultra order transaction; Jupiter lands it and reports the result
+    pub async fn execute(
+        signed_transaction_b64: &str,
+        request_id: &str,
+    ) -> Result<JupiterUltraExecuteResponse, String> {
+        let body = serde_json::json!({
+            "signedTransaction": signed_transaction_b64,
+            "requestId": request_id,
+        });
+
+        log::info!("Executing Jupiter Ultra order {}", request_id);
+
+        let url = format!("{}/execute", config::endpoints().jupiter_ultra_api);
+        let response = with_api_key(Request::post(&url))
+            .json(&body)
+            .map_err(|e| format!("Failed to build Jupiter execute request: {:?}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Jupiter execute failed: {:?}", e))?;
+
+        if !response.ok() {
+            return Err(format!("Jupiter API error: {}", response.status()));
+        }
+
+        let result = response
+            .json::<JupiterUltraExecuteResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse Jupiter execute response: {:?}", e))?;
+
+        log::info!("Jupiter execute status: {} ({:?})", result.status, result.signature);
+
+        Ok(result)
+    }
+}
+
+impl JupiterUltraOrderResponse {
+    pub fn to_quote(&self) -> Result<Quote, String> {
+        let in_amount = parse_amount_str(&self.in_amount, "inAmount")?;
+        let out_amount = parse_amount_str(&self.out_amount, "outAmount")?;
+        // otherAmountThreshold bounds whichever side was not fixed.
+        let threshold = parse_amount_str(&self.other_amount_threshold, "otherAmountThreshold")?;
+        let swap_mode = if self.swap_mode == "ExactOut" {
+            SwapMode::ExactOut
+        } else {
+            SwapMode::ExactIn
+        };
+        let (min_out_amount, max_in_amount) = match swap_mode {
+            SwapMode::ExactIn => (threshold, in_amount),
+            SwapMode::ExactOut => (out_amount, threshold),
+        };
+        Ok(Quote {
+            provider: ProviderId::Jupiter,
+            swap_mode,
+            input_mint: self.input_mint.clone(),
+            output_mint: self.output_mint.clone(),
+            in_amount,
+            out_amount,
+            min_out_amount,
+            max_in_amount,
+            slippage_bps: self.slippage_bps,
+            price_impact_pct: self.price_impact,
+            platform_fee_bps: self.fee_bps,
+            priority_fee_lamports: self.prioritization_fee_lamports,
+            route: self.route_plan.iter().map(JupiterRoutePlanStep::to_leg).collect(),
+            expires_at_ms: expiry_from_now(JUPITER_QUOTE_TTL_MS),
+            request_id: Some(self.request_id.clone()),
+            transaction: self.transaction.clone(),
+        })
+    }
+}
+
+impl QuoteProvider for JupiterClient {
+    fn id(&self) -> ProviderId {
+        ProviderId::Jupiter
+    }
+
+    fn supports_exact_out(&self) -> bool {
+        true
+    }
+
+    fn fetch_quote<'a>(&'a self, req: &'a QuoteRequest) -> LocalBoxFuture<'a, Result<Quote, String>> {
+        Box::pin(async move {
+            let order = Self::get_quote(
+                &req.input_mint,
+                &req.output_mint,
+                req.amount,
+                req.swap_mode,
+                (!req.auto_slippage).then_some(req.slippage_bps),
+                req.taker.as_deref(),
+            )
+            .await?;
+            if let Some(err) = &order.error_message {
+                return Err(format!("Jupiter: {}", err));
+            }
+            order.to_quote()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(compute_budget: &[Instruction]) -> JupiterSwapInstructionsResponse {
+        let encode = |ix: &Instruction| {
+            serde_json::json!({
+                "programId": ix.program_id.to_string(),
+                "accounts": [],
+                "data": B64.encode(&ix.data),
+            })
+        };
+        serde_json::from_value(serde_json::json!({
+            "computeBudgetInstructions": compute_budget.iter().map(encode).collect::<Vec<_>>(),
+            "swapInstruction": encode(&Instruction::new_with_bytes(Pubkey::new_unique(), &[7], vec![])),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compute_budget_covers_the_whole_transaction() {
+        let swap = response(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(250_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        ]);
+        assert_eq!(swap.swap_instructions().unwrap().len(), 1);
+        assert_eq!(
+            swap.compute_budget(100_000).unwrap(),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(350_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            ]
+        );
+        assert_eq!(
+            swap.compute_budget(u32::MAX).unwrap(),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNITS),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            ]
+        );
+        assert_eq!(
+            response(&[]).compute_budget(100_000).unwrap(),
+            vec![ComputeBudgetInstruction::set_compute_unit_limit(
+                DEFAULT_SWAP_COMPUTE_UNITS + 100_000
+            )]
+        );
+    }
+}
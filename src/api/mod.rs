@@ -1,7 +1,12 @@
 pub mod jupiter;
 pub mod dflow;
 pub mod titan;
+pub mod quote;
+pub mod ranking;
+pub mod slippage;
+pub mod execute;
 
 pub use jupiter::JupiterClient;
 pub use dflow::DflowClient;
-pub use titan::TitanClient;
\ No newline at end of file
+pub use titan::TitanClient;
+pub use quote::{default_providers, Quote, QuoteRequest};
@@ -0,0 +1,248 @@
+// Provider-agnostic quote model shared by all aggregator clients.
+use futures::future::{select, Either, LocalBoxFuture};
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::{DflowClient, JupiterClient, TitanClient};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProviderId {
+    Jupiter,
+    Dflow,
+    Titan,
+}
+
+impl ProviderId {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProviderId::Jupiter => "Jupiter",
+            ProviderId::Dflow => "Dflow",
+            ProviderId::Titan => "Titan",
+        }
+    }
+}
+
+/// Which side of the swap the user fixed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SwapMode {
+    #[default]
+    ExactIn,
+    ExactOut,
+}
+
+impl SwapMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapMode::ExactIn => "ExactIn",
+            SwapMode::ExactOut => "ExactOut",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteRequest {
+    pub input_mint: String,
+    pub output_mint: String,
+    /// Input amount for ExactIn, desired output amount for ExactOut.
+    pub amount: u64,
+    pub swap_mode: SwapMode,
+    /// Explicit tolerance for providers that need one.
+    pub slippage_bps: u16,
+    /// Let providers that recommend their own slippage choose it.
+    pub auto_slippage: bool,
+    /// Wallet that will sign the swap, for providers that build transactions.
+    pub taker: Option<String>,
+}
+
+/// One hop of a provider's route. Amounts are in base units of the hop's
+/// own mints; `percent` is the share of the input sent down this hop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteLeg {
+    pub label: String,
+    pub amm_key: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub percent: u8,
+}
+
+/// A quote normalized across providers. Amounts are in base units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub provider: ProviderId,
+    pub swap_mode: SwapMode,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub min_out_amount: u64,
+    /// Most input that can be spent: `in_amount` for ExactIn, the slippage
+    /// bound for ExactOut.
+    pub max_in_amount: u64,
+    /// Slippage tolerance the provider applied to `min_out_amount`.
+    pub slippage_bps: u16,
+    pub price_impact_pct: Option<f64>,
+    pub platform_fee_bps: u16,
+    pub priority_fee_lamports: u64,
+    pub route: Vec<RouteLeg>,
+    pub expires_at_ms: f64,
+    /// Provider handle needed to execute this quote, if any.
+    pub request_id: Option<String>,
+    /// Unsigned base64 transaction when the provider returns one with the quote.
+    pub transaction: Option<String>,
+}
+
+impl Quote {
+    pub fn is_expired(&self, now_ms: f64) -> bool {
+        now_ms >= self.expires_at_ms
+    }
+
+    /// Whether `execute_quote` can land this quote. Dflow and Titan are
+    /// quoted for comparison only and come back without a transaction.
+    pub fn is_executable(&self) -> bool {
+        self.transaction.is_some()
+    }
+}
+
+pub const DEFAULT_QUOTE_TIMEOUT_MS: u64 = 5_000;
+
+pub trait QuoteProvider {
+    fn id(&self) -> ProviderId;
+
+    fn timeout_ms(&self) -> u64 {
+        DEFAULT_QUOTE_TIMEOUT_MS
+    }
+
+    fn supports_exact_out(&self) -> bool {
+        false
+    }
+
+    fn fetch_quote<'a>(
+        &'a self,
+        req: &'a QuoteRequest,
+    ) -> LocalBoxFuture<'a, Result<Quote, String>>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuoteStatus {
+    Pending,
+    Ready(Quote),
+    Failed(String),
+    TimedOut,
+    /// The provider cannot quote this request (e.g. no ExactOut support).
+    Unsupported,
+}
+
+/// Latest outcome for one provider, as shown in the comparison list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderQuote {
+    pub provider: ProviderId,
+    pub status: QuoteStatus,
+    pub latency_ms: Option<f64>,
+}
+
+impl ProviderQuote {
+    pub fn pending(provider: ProviderId) -> Self {
+        Self {
+            provider,
+            status: QuoteStatus::Pending,
+            latency_ms: None,
+        }
+    }
+
+    pub fn quote(&self) -> Option<&Quote> {
+        match &self.status {
+            QuoteStatus::Ready(q) => Some(q),
+            _ => None,
+        }
+    }
+}
+
+/// Fetches one quote, giving up after the provider's timeout.
+pub async fn fetch_with_timeout(provider: &dyn QuoteProvider, req: &QuoteRequest) -> ProviderQuote {
+    if req.swap_mode == SwapMode::ExactOut && !provider.supports_exact_out() {
+        return ProviderQuote {
+            provider: provider.id(),
+            status: QuoteStatus::Unsupported,
+            latency_ms: None,
+        };
+    }
+    let started = js_sys::Date::now();
+    let timeout = gloo_timers::future::sleep(Duration::from_millis(provider.timeout_ms()));
+    let status = match select(provider.fetch_quote(req), Box::pin(timeout)).await {
+        Either::Left((Ok(quote), _)) => QuoteStatus::Ready(quote),
+        Either::Left((Err(e), _)) => QuoteStatus::Failed(e),
+        Either::Right(_) => QuoteStatus::TimedOut,
+    };
+    ProviderQuote {
+        provider: provider.id(),
+        status,
+        latency_ms: Some(js_sys::Date::now() - started),
+    }
+}
+
+/// All aggregators shown in the swap UI.
+pub fn default_providers() -> Vec<Rc<dyn QuoteProvider>> {
+    vec![
+        Rc::new(JupiterClient),
+        Rc::new(DflowClient),
+        Rc::new(TitanClient),
+    ]
+}
+
+pub fn parse_amount_str(value: &str, field: &str) -> Result<u64, String> {
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("invalid {} '{}'", field, value))
+}
+
+/// Minimum output implied by `slippage_bps` when a provider doesn't report one.
+pub fn min_out_from_slippage(out_amount: u64, slippage_bps: u16) -> u64 {
+    let kept = 10_000u128.saturating_sub(slippage_bps as u128);
+    ((out_amount as u128 * kept) / 10_000) as u64
+}
+
+/// Maximum input implied by `slippage_bps` for an ExactOut quote.
+pub fn max_in_from_slippage(in_amount: u64, slippage_bps: u16) -> u64 {
+    ((in_amount as u128 * (10_000 + slippage_bps as u128)) / 10_000) as u64
+}
+
+pub fn expiry_from_now(ttl_ms: f64) -> f64 {
+    js_sys::Date::now() + ttl_ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slippage_bounds() {
+        assert_eq!(min_out_from_slippage(1_000_000, 50), 995_000);
+        assert_eq!(min_out_from_slippage(1_000_000, 10_000), 0);
+        assert_eq!(min_out_from_slippage(u64::MAX, 0), u64::MAX);
+        assert_eq!(max_in_from_slippage(1_000_000, 50), 1_005_000);
+        assert_eq!(max_in_from_slippage(1_000_000, 0), 1_000_000);
+    }
+
+    #[test]
+    fn parses_provider_amounts() {
+        assert_eq!(parse_amount_str("42", "outAmount").unwrap(), 42);
+        assert_eq!(
+            parse_amount_str("4.2", "outAmount").unwrap_err(),
+            "invalid outAmount '4.2'"
+        );
+    }
+
+    #[test]
+    fn only_ready_statuses_carry_a_quote() {
+        let pending = ProviderQuote::pending(ProviderId::Titan);
+        assert_eq!(pending.status, QuoteStatus::Pending);
+        assert!(pending.quote().is_none());
+        let failed = ProviderQuote {
+            status: QuoteStatus::Failed("boom".to_string()),
+            ..pending
+        };
+        assert!(failed.quote().is_none());
+    }
+}
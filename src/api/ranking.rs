@@ -0,0 +1,216 @@
+// Ranks normalized quotes by what the user actually ends up with.
+use std::cmp::Ordering;
+
+use super::quote::{ProviderId, Quote, SwapMode};
+use crate::token_registry::SOL_MINT;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedQuote {
+    pub quote: Quote,
+    pub net_out: u64,
+    pub net_in: u64,
+    /// Shortfall versus the best quote, in basis points: less output for
+    /// ExactIn, more input for ExactOut.
+    pub spread_bps: u32,
+    pub stale: bool,
+}
+
+/// Output after the platform fee and the priority fee. The priority fee is
+/// paid in SOL, so it is only deducted when it can be priced from the quote
+/// itself (SOL on either side); price impact is already reflected in
+/// `out_amount` and only breaks ties.
+pub fn net_output(quote: &Quote) -> u64 {
+    let platform_fee = (quote.out_amount as u128 * quote.platform_fee_bps as u128 / 10_000) as u64;
+    let after_fee = quote.out_amount.saturating_sub(platform_fee);
+    let priority_in_out = if quote.output_mint == SOL_MINT {
+        quote.priority_fee_lamports
+    } else if quote.input_mint == SOL_MINT && quote.in_amount > 0 {
+        (quote.priority_fee_lamports as u128 * quote.out_amount as u128 / quote.in_amount as u128)
+            as u64
+    } else {
+        0
+    };
+    after_fee.saturating_sub(priority_in_out)
+}
+
+/// Input cost including the priority fee when the input is SOL.
+pub fn net_input(quote: &Quote) -> u64 {
+    if quote.input_mint == SOL_MINT {
+        quote.in_amount.saturating_add(quote.priority_fee_lamports)
+    } else {
+        quote.in_amount
+    }
+}
+
+/// Orders quotes best-first: fresh before stale, then higher net output
+/// (lower net input for ExactOut), then lower price impact, then a fixed provider order so equal quotes
+/// always rank the same way.
+pub fn rank_quotes(quotes: &[Quote], now_ms: f64) -> Vec<RankedQuote> {
+    let mut ranked: Vec<RankedQuote> = quotes
+        .iter()
+        .map(|q| RankedQuote {
+            quote: q.clone(),
+            net_out: net_output(q),
+            net_in: net_input(q),
+            spread_bps: 0,
+            stale: q.is_expired(now_ms),
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        a.stale
+            .cmp(&b.stale)
+            .then_with(|| match a.quote.swap_mode {
+                SwapMode::ExactIn => b.net_out.cmp(&a.net_out),
+                SwapMode::ExactOut => a.net_in.cmp(&b.net_in),
+            })
+            .then_with(|| {
+                impact(&a.quote)
+                    .partial_cmp(&impact(&b.quote))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| provider_order(a.quote.provider).cmp(&provider_order(b.quote.provider)))
+    });
+
+    if let Some(best) = ranked.first().cloned() {
+        for r in ranked.iter_mut() {
+            r.spread_bps = match r.quote.swap_mode {
+                SwapMode::ExactIn => spread_bps(best.net_out, r.net_out),
+                SwapMode::ExactOut => cost_spread_bps(best.net_in, r.net_in),
+            };
+        }
+    }
+    ranked
+}
+
+/// The highest-ranked quote the app can actually execute. Rates from
+/// quote-only providers are still ranked so the comparison stays honest.
+pub fn best_executable(ranked: &[RankedQuote]) -> Option<&RankedQuote> {
+    ranked.iter().find(|r| r.quote.is_executable())
+}
+
+pub fn spread_bps(best: u64, other: u64) -> u32 {
+    if best == 0 || other >= best {
+        return 0;
+    }
+    ((best - other) as u128 * 10_000 / best as u128) as u32
+}
+
+pub fn cost_spread_bps(best: u64, other: u64) -> u32 {
+    if best == 0 || other <= best {
+        return 0;
+    }
+    ((other - best) as u128 * 10_000 / best as u128) as u32
+}
+
+fn impact(quote: &Quote) -> f64 {
+    quote.price_impact_pct.map(f64::abs).unwrap_or(f64::MAX)
+}
+
+fn provider_order(provider: ProviderId) -> u8 {
+    match provider {
+        ProviderId::Jupiter => 0,
+        ProviderId::Dflow => 1,
+        ProviderId::Titan => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn quote(provider: ProviderId, out_amount: u64) -> Quote {
+        Quote {
+            provider,
+            swap_mode: SwapMode::ExactIn,
+            input_mint: USDC_MINT.to_string(),
+            output_mint: SOL_MINT.to_string(),
+            in_amount: 1_000_000,
+            out_amount,
+            min_out_amount: out_amount,
+            max_in_amount: 1_000_000,
+            slippage_bps: 50,
+            price_impact_pct: Some(0.1),
+            platform_fee_bps: 0,
+            priority_fee_lamports: 0,
+            route: Vec::new(),
+            expires_at_ms: 10_000.0,
+            request_id: None,
+            transaction: Some("tx".to_string()),
+        }
+    }
+
+    #[test]
+    fn ranks_by_net_output_after_fees() {
+        let mut jupiter = quote(ProviderId::Jupiter, 1_000_000);
+        jupiter.platform_fee_bps = 100;
+        let titan = quote(ProviderId::Titan, 995_000);
+        let ranked = rank_quotes(&[jupiter, titan], 0.0);
+        assert_eq!(ranked[0].quote.provider, ProviderId::Titan);
+        assert_eq!(ranked[1].net_out, 990_000);
+        assert_eq!(ranked[1].spread_bps, 50);
+    }
+
+    #[test]
+    fn deducts_priority_fee_when_output_is_sol() {
+        let mut q = quote(ProviderId::Jupiter, 1_000_000);
+        q.priority_fee_lamports = 5_000;
+        assert_eq!(net_output(&q), 995_000);
+        q.output_mint = USDC_MINT.to_string();
+        assert_eq!(net_output(&q), 1_000_000);
+    }
+
+    #[test]
+    fn stale_quotes_rank_last() {
+        let mut expired = quote(ProviderId::Jupiter, 2_000_000);
+        expired.expires_at_ms = 5.0;
+        let ranked = rank_quotes(&[expired, quote(ProviderId::Dflow, 1_000_000)], 5.0);
+        assert_eq!(ranked[0].quote.provider, ProviderId::Dflow);
+        assert!(ranked[1].stale);
+    }
+
+    #[test]
+    fn exact_out_prefers_lower_input() {
+        let mut cheap = quote(ProviderId::Titan, 1_000);
+        cheap.swap_mode = SwapMode::ExactOut;
+        cheap.in_amount = 990_000;
+        let mut dear = cheap.clone();
+        dear.provider = ProviderId::Jupiter;
+        dear.in_amount = 1_000_000;
+        let ranked = rank_quotes(&[dear, cheap], 0.0);
+        assert_eq!(ranked[0].quote.provider, ProviderId::Titan);
+        assert_eq!(ranked[1].spread_bps, cost_spread_bps(990_000, 1_000_000));
+        assert_eq!(ranked[1].spread_bps, 101);
+    }
+
+    #[test]
+    fn ties_break_on_impact_then_provider() {
+        let mut jupiter = quote(ProviderId::Jupiter, 1_000);
+        jupiter.price_impact_pct = Some(0.5);
+        let dflow = quote(ProviderId::Dflow, 1_000);
+        let titan = quote(ProviderId::Titan, 1_000);
+        let order: Vec<ProviderId> = rank_quotes(&[titan, jupiter, dflow], 0.0)
+            .iter()
+            .map(|r| r.quote.provider)
+            .collect();
+        assert_eq!(order, [ProviderId::Dflow, ProviderId::Titan, ProviderId::Jupiter]);
+    }
+
+    #[test]
+    fn best_executable_skips_quote_only_providers() {
+        let mut dflow = quote(ProviderId::Dflow, 1_100);
+        dflow.transaction = None;
+        let ranked = rank_quotes(&[quote(ProviderId::Jupiter, 1_000), dflow], 0.0);
+        assert_eq!(ranked[0].quote.provider, ProviderId::Dflow);
+        assert_eq!(best_executable(&ranked).unwrap().quote.provider, ProviderId::Jupiter);
+    }
+
+    #[test]
+    fn best_executable_is_none_without_transactions() {
+        let mut titan = quote(ProviderId::Titan, 1_000);
+        titan.transaction = None;
+        assert!(best_executable(&rank_quotes(&[titan], 0.0)).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+// Slippage tolerance chosen in the swap settings.
+use crate::token_registry::{SOL_MINT, USDC_MINT};
+
+pub const SLIPPAGE_PRESETS_BPS: [u16; 3] = [10, 50, 100];
+/// Above this the UI warns that the trade may fill far from the quote.
+pub const HIGH_SLIPPAGE_BPS: u16 = 300;
+pub const MAX_SLIPPAGE_BPS: u16 = 5_000;
+
+const STABLE_MINTS: [&str; 2] = [USDC_MINT, "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"];
+const MAJOR_MINTS: [&str; 4] = [
+    SOL_MINT,
+    "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So",
+    "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn",
+    "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Slippage {
+    /// Provider recommendation where available, otherwise a pair-based default.
+    #[default]
+    Auto,
+    Fixed(u16),
+}
+
+impl Slippage {
+    pub fn is_auto(&self) -> bool {
+        matches!(self, Slippage::Auto)
+    }
+
+    /// Tolerance sent to providers that need an explicit value.
+    pub fn bps_for(&self, input_mint: &str, output_mint: &str) -> u16 {
+        match self {
+            Slippage::Auto => auto_slippage_bps(input_mint, output_mint),
+            Slippage::Fixed(bps) => *bps,
+        }
+    }
+
+    /// Parses a percentage typed by the user, e.g. "0.75".
+    pub fn from_percent(value: &str) -> Result<Self, String> {
+        let pct: f64 = value
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| "invalid slippage".to_string())?;
+        let bps = (pct * 100.0).round();
+        if bps <= 0.0 || bps > MAX_SLIPPAGE_BPS as f64 {
+            return Err(format!(
+                "slippage must be between 0.01% and {}%",
+                MAX_SLIPPAGE_BPS / 100
+            ));
+        }
+        Ok(Slippage::Fixed(bps as u16))
+    }
+}
+
+/// Rough volatility buckets: stable pairs barely move, majors move a little,
+/// anything else gets a wider default.
+pub fn auto_slippage_bps(input_mint: &str, output_mint: &str) -> u16 {
+    let class = |mint: &str| {
+        if STABLE_MINTS.contains(&mint) {
+            0
+        } else if MAJOR_MINTS.contains(&mint) {
+            1
+        } else {
+            2
+        }
+    };
+    match class(input_mint).max(class(output_mint)) {
+        0 => 10,
+        1 => 50,
+        _ => 150,
+    }
+}
+
+pub fn format_bps(bps: u16) -> String {
+    format!("{}%", bps as f64 / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BONK_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+    #[test]
+    fn auto_buckets_follow_the_more_volatile_side() {
+        assert_eq!(auto_slippage_bps(USDC_MINT, USDT_MINT), 10);
+        assert_eq!(auto_slippage_bps(SOL_MINT, USDC_MINT), 50);
+        assert_eq!(auto_slippage_bps(USDC_MINT, SOL_MINT), 50);
+        assert_eq!(auto_slippage_bps(SOL_MINT, BONK_MINT), 150);
+    }
+
+    #[test]
+    fn fixed_overrides_auto() {
+        assert_eq!(Slippage::Fixed(30).bps_for(SOL_MINT, BONK_MINT), 30);
+        assert_eq!(Slippage::Auto.bps_for(SOL_MINT, BONK_MINT), 150);
+        assert!(Slippage::default().is_auto());
+    }
+
+    #[test]
+    fn parses_percentages() {
+        assert_eq!(Slippage::from_percent("0.75").unwrap(), Slippage::Fixed(75));
+        assert_eq!(Slippage::from_percent(" 1% ").unwrap(), Slippage::Fixed(100));
+        assert_eq!(Slippage::from_percent("50").unwrap(), Slippage::Fixed(MAX_SLIPPAGE_BPS));
+        assert!(Slippage::from_percent("0").is_err());
+        assert!(Slippage::from_percent("50.01").is_err());
+        assert!(Slippage::from_percent("abc").is_err());
+    }
+
+    #[test]
+    fn formats_bps_as_percent() {
+        assert_eq!(format_bps(50), "0.5%");
+        assert_eq!(format_bps(100), "1%");
+    }
+}
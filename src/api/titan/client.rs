@@ -1,8 +1,15 @@
 // Titan client - uses hermes HTTP proxy for quotes
 use super::types::*;
 use gloo_net::http::Request;
+use futures::future::LocalBoxFuture;
 
-const HERMES_TITAN_ENDPOINT: &str = "https://hermes-titan-proxy.fly.dev/api/titan/quote";
+use crate::api::quote::{
+    expiry_from_now, max_in_from_slippage, min_out_from_slippage, parse_amount_str, ProviderId,
+    Quote, QuoteProvider, QuoteRequest, SwapMode,
+};
+use crate::config;
+
+const TITAN_QUOTE_TTL_MS: f64 = 20_000.0;
 
 pub struct TitanClient;
 
@@ -12,10 +19,11 @@ impl TitanClient {
     }
 
     pub async fn get_quote(
-        &mut self,
+        &self,
         input_mint: &str,
         output_mint: &str,
         amount: u64,
+        swap_mode: SwapMode,
         slippage_bps: u16,
     ) -> Result<TitanQuoteResponse, String> {
         log::info!("Fetching Titan quote via hermes HTTP proxy");
@@ -24,10 +32,11 @@ impl TitanClient {
             "inputMint": input_mint,
             "outputMint": output_mint,
             "amount": amount.to_string(),
+            "swapMode": swap_mode.as_str(),
             "slippageBps": slippage_bps
         });
 
-        let response = Request::post(HERMES_TITAN_ENDPOINT)
+        let response = Request::post(&config::endpoints().titan_quote_url)
             .json(&request_body)
             .map_err(|e| format!("Failed to build Titan request: {:?}", e))?
             .send()
@@ -49,6 +58,70 @@ impl TitanClient {
     }
 }
 
+impl TitanQuoteResponse {
+    pub fn to_quote(&self, req: &QuoteRequest) -> Result<Quote, String> {
+        let in_amount = parse_amount_str(&self.in_amount, "inAmount")?;
+        let out_amount = parse_amount_str(&self.out_amount, "outAmount")?;
+        let threshold = match &self.other_amount_threshold {
+            Some(v) => Some(parse_amount_str(v, "otherAmountThreshold")?),
+            None => None,
+        };
+        let (min_out_amount, max_in_amount) = match req.swap_mode {
+            SwapMode::ExactIn => (
+                threshold.unwrap_or_else(|| min_out_from_slippage(out_amount, req.slippage_bps)),
+                in_amount,
+            ),
+            SwapMode::ExactOut => (
+                out_amount,
+                threshold.unwrap_or_else(|| max_in_from_slippage(in_amount, req.slippage_bps)),
+            ),
+        };
+        Ok(Quote {
+            provider: ProviderId::Titan,
+            swap_mode: req.swap_mode,
+            input_mint: req.input_mint.clone(),
+            output_mint: req.output_mint.clone(),
+            in_amount,
+            out_amount,
+            min_out_amount,
+            max_in_amount,
+            slippage_bps: req.slippage_bps,
+            price_impact_pct: self.price_impact_pct.as_deref().and_then(|v| v.parse().ok()),
+            platform_fee_bps: 0,
+            priority_fee_lamports: 0,
+            route: Vec::new(),
+            expires_at_ms: expiry_from_now(TITAN_QUOTE_TTL_MS),
+            request_id: None,
+            transaction: None,
+        })
+    }
+}
+
+impl QuoteProvider for TitanClient {
+    fn id(&self) -> ProviderId {
+        ProviderId::Titan
+    }
+
+    fn supports_exact_out(&self) -> bool {
+        true
+    }
+
+    fn fetch_quote<'a>(&'a self, req: &'a QuoteRequest) -> LocalBoxFuture<'a, Result<Quote, String>> {
+        Box::pin(async move {
+            let quote = self
+                .get_quote(
+                    &req.input_mint,
+                    &req.output_mint,
+                    req.amount,
+                    req.swap_mode,
+                    req.slippage_bps,
+                )
+                .await?;
+            quote.to_quote(req)
+        })
+    }
+}
+
 impl Default for TitanClient {
     fn default() -> Self {
         Self::new()
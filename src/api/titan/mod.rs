@@ -1,5 +1,4 @@
 pub mod client;
 pub mod types;
 
-pub use client::TitanClient;
-pub use types::*;
\ No newline at end of file
+pub use client::TitanClient;
\ No newline at end of file
@@ -8,4 +8,8 @@ pub struct TitanQuoteResponse {
     pub out_amount: String,
     #[serde(rename = "inAmount")]
     pub in_amount: String,
+    #[serde(rename = "otherAmountThreshold", default)]
+    pub other_amount_threshold: Option<String>,
+    #[serde(rename = "priceImpactPct", default)]
+    pub price_impact_pct: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+// Holdings of the connected wallet, used by the swap form for display,
+// Max/Half shortcuts and balance validation.
+use std::collections::HashMap;
+
+use crate::rpc::{get_balance, get_token_accounts_by_owner};
+use crate::timed_delegation::TOKEN_PROGRAM_ID;
+use crate::token_registry::SOL_MINT;
+
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+/// Lamports kept back from "Max" so the wallet can still pay fees and rent
+/// for any accounts the swap creates.
+pub const SOL_RESERVE_LAMPORTS: u64 = 10_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WalletBalances {
+    pub lamports: u64,
+    /// Base-unit balance per mint, summed across the owner's token accounts.
+    pub tokens: HashMap<String, u64>,
+}
+
+impl WalletBalances {
+    pub fn balance_of(&self, mint: &str) -> u64 {
+        if mint == SOL_MINT {
+            self.lamports
+        } else {
+            self.tokens.get(mint).copied().unwrap_or(0)
+        }
+    }
+
+    /// Largest amount of `mint` that can be swapped, leaving the SOL reserve.
+    pub fn spendable(&self, mint: &str) -> u64 {
+        let balance = self.balance_of(mint);
+        if mint == SOL_MINT {
+            balance.saturating_sub(SOL_RESERVE_LAMPORTS)
+        } else {
+            balance
+        }
+    }
+}
+
+pub async fn fetch_balances(rpc_url: &str, owner: &str) -> Result<WalletBalances, String> {
+    let lamports = get_balance(rpc_url, owner).await?;
+    let mut tokens = HashMap::new();
+    for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+        for account in get_token_accounts_by_owner(rpc_url, owner, program).await? {
+            *tokens.entry(account.mint).or_insert(0) += account.amount;
+        }
+    }
+    Ok(WalletBalances { lamports, tokens })
+}
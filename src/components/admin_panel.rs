@@ -0,0 +1,173 @@
+use dioxus::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::config::{self, rpc_url};
+use crate::program_config::{
+    build_initialize_config_instruction, build_update_config_instruction, config_address,
+    fetch_program_config, ProgramConfig,
+};
+use crate::timed_delegation::JsInstruction;
+use crate::wallet_context::use_wallet;
+
+const INPUT_STYLE: &str = "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;";
+
+/// Reads the program's config account and builds the initialize/update
+/// instruction for the admin to send, or to copy into a multisig.
+#[component]
+pub fn AdminPanel() -> Element {
+    let wallet = use_wallet();
+    let program_id = config::program_id();
+    let config_pda = Pubkey::from_str(&program_id)
+        .map(|p| config_address(&p).to_string())
+        .unwrap_or_default();
+    // Outer `None` until the first fetch, inner `None` when uninitialized.
+    let mut current = use_signal(|| Option::<Option<ProgramConfig>>::None);
+    let mut admin = use_signal(String::new);
+    let mut fee_recipient = use_signal(String::new);
+    let mut fee_bps = use_signal(|| "0".to_string());
+    let mut paused = use_signal(|| false);
+    let mut instruction_json = use_signal(|| Option::<String>::None);
+    let mut status = use_signal(|| Option::<String>::None);
+    let mut busy = use_signal(|| false);
+
+    let program_id_for_refresh = program_id.clone();
+    let refresh = move || {
+        let program_id = program_id_for_refresh.clone();
+        spawn(async move {
+            let Ok(program_id) = Pubkey::from_str(&program_id) else {
+                status.set(Some("Invalid program id in config".to_string()));
+                return;
+            };
+            match fetch_program_config(&rpc_url(), &program_id).await {
+                Ok(Some(cfg)) => {
+                    admin.set(cfg.admin.to_string());
+                    fee_recipient.set(cfg.fee_recipient.to_string());
+                    fee_bps.set(cfg.fee_bps.to_string());
+                    paused.set(cfg.paused);
+                    current.set(Some(Some(cfg)));
+                }
+                Ok(None) => current.set(Some(None)),
+                Err(e) => status.set(Some(e)),
+            }
+        });
+    };
+
+    use_hook(&refresh);
+
+    let program_id_for_build = program_id.clone();
+    let build_instruction = move || -> Result<(String, String), String> {
+        let signer = wallet.address().ok_or("Connect the admin wallet first")?;
+        let parse = |label: &str, v: &str| {
+            Pubkey::from_str(v.trim()).map_err(|_| format!("Invalid {} pubkey", label))
+        };
+        let program_id = parse("program", &program_id_for_build)?;
+        let signer_key = parse("wallet", &signer)?;
+        let recipient = parse("fee recipient", &fee_recipient.read())?;
+        let fee: u16 = fee_bps
+            .read()
+            .trim()
+            .parse()
+            .map_err(|_| "Fee must be a whole number of bps")?;
+
+        let ix: JsInstruction = match current.read().clone() {
+            Some(Some(existing)) => {
+                if existing.admin != signer_key {
+                    return Err(format!("Connected wallet is not the admin ({})", existing.admin));
+                }
+                let updated = ProgramConfig {
+                    admin: parse("admin", &admin.read())?,
+                    fee_recipient: recipient,
+                    fee_bps: fee,
+                    paused: *paused.read(),
+                };
+                build_update_config_instruction(&program_id, &signer_key, &updated)?
+            }
+            Some(None) => build_initialize_config_instruction(&program_id, &signer_key, &recipient, fee)?,
+            None => return Err("Config account not loaded yet".to_string()),
+        };
+        let json = serde_json::to_string(&vec![ix]).map_err(|e| e.to_string())?;
+        Ok((signer, json))
+    };
+
+    let build_instruction_for_show = build_instruction.clone();
+    let show_instruction = move |_| match build_instruction_for_show() {
+        Ok((_, json)) => {
+            instruction_json.set(Some(json));
+            status.set(None);
+        }
+        Err(e) => status.set(Some(e)),
+    };
+
+    let build_instruction_for_send = build_instruction;
+    let send_instruction = move |_| {
+        let (signer, json) = match build_instruction_for_send() {
+            Ok(v) => v,
+            Err(e) => {
+                status.set(Some(e));
+                return;
+            }
+        };
+        let adapter = wallet.adapter.read().clone();
+        let refresh = refresh.clone();
+        spawn(async move {
+            busy.set(true);
+            match adapter.send_instructions_json(&rpc_url(), &signer, &json).await {
+                Ok(sig) => {
+                    status.set(Some(format!("Config updated: {}", sig)));
+                    refresh();
+                }
+                Err(e) => status.set(Some(e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let initialized = matches!(*current.read(), Some(Some(_)));
+
+    rsx! {
+        div { style: "display: grid; gap: 8px; margin-bottom: 16px;",
+            div { style: "padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155; display: grid; gap: 4px; font-size: 12px; color: #cbd5f5; word-break: break-all;",
+                div { "Program: {program_id}" }
+                div { "Config: {config_pda}" }
+                match current.read().as_ref() {
+                    None => rsx! { div { style: "color: #64748b;", "Loading config..." } },
+                    Some(None) => rsx! { div { style: "color: #f59e0b;", "Not initialized" } },
+                    Some(Some(cfg)) => rsx! {
+                        div { "Admin: {cfg.admin}" }
+                        div { "Fee: {cfg.fee_bps} bps to {cfg.fee_recipient}" }
+                        div { style: if cfg.paused { "color: #f87171; font-weight: 600;" } else { "color: #a7f3d0;" },
+                            if cfg.paused { "Paused" } else { "Active" }
+                        }
+                    },
+                }
+            }
+            if initialized {
+                input { value: "{admin}", oninput: move |e| admin.set(e.value()), placeholder: "Admin pubkey", style: INPUT_STYLE }
+            }
+            input { value: "{fee_recipient}", oninput: move |e| fee_recipient.set(e.value()), placeholder: "Fee recipient wallet", style: INPUT_STYLE }
+            input { value: "{fee_bps}", oninput: move |e| fee_bps.set(e.value()), placeholder: "Fee (bps)", style: INPUT_STYLE }
+            if initialized {
+                label { style: "display: flex; align-items: center; gap: 8px; font-size: 12px; color: #94a3b8;",
+                    input { r#type: "checkbox", checked: *paused.read(), onchange: move |e| paused.set(e.checked()) }
+                    "Pause delegations and withdraws"
+                }
+            }
+            div { style: "display: flex; gap: 8px;",
+                button { onclick: show_instruction, style: "flex: 1; padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Show instruction" }
+                button {
+                    onclick: send_instruction,
+                    disabled: *busy.read(),
+                    style: "flex: 1; padding: 10px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none;",
+                    if initialized { "Send update" } else { "Initialize" }
+                }
+            }
+            if let Some(json) = instruction_json.read().as_ref() {
+                textarea { readonly: true, value: "{json}", rows: "6", style: "{INPUT_STYLE} font-family: monospace; font-size: 11px;" }
+            }
+            if let Some(msg) = status.read().as_ref() {
+                div { style: "color: #fca5a5; font-size: 12px; word-break: break-all;", "{msg}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use dioxus::prelude::*;
+
+use crate::config::{self, Cluster};
+
+#[component]
+pub fn ClusterSelector(cluster: Signal<Cluster>) -> Element {
+    let active = *cluster.read();
+
+    rsx! {
+        div { style: "display: flex; gap: 6px; justify-content: center; margin-bottom: 12px;",
+            for option in Cluster::ALL {
+                button {
+                    key: "{option.as_str()}",
+                    onclick: move |_| {
+                        config::set_cluster(option);
+                        cluster.set(option);
+                    },
+                    style: if option == active { "padding: 4px 12px; border-radius: 999px; background: #1d4ed8; color: #fff; border: none; font-size: 12px;" } else { "padding: 4px 12px; border-radius: 999px; background: #0f172a; color: #94a3b8; border: 1px solid #334155; font-size: 12px; cursor: pointer;" },
+                    "{option.label()}"
+                }
+            }
+        }
+        if active != Cluster::Mainnet {
+            div { style: "color: #94a3b8; font-size: 11px; text-align: center; margin-bottom: 12px;",
+                "{config::rpc_url()}"
+            }
+        }
+    }
+}
@@ -5,47 +5,211 @@ use js_sys::Date;
 use serde_json::to_string;
 use sha2::{Digest, Sha256};
 
-use crate::rpc::get_account_data_base64;
+use crate::api::slippage::auto_slippage_bps;
+use crate::components::{AdminPanel, LedgerPanel, WalletPicker};
+use crate::config::{self, rpc_url};
+use crate::handoff::{self, HandoffState, MobileWallet};
+use crate::ledger::{fetch_ledger, Ledger};
+use crate::payment_request::PaymentRequest;
+use crate::program_config::{check_not_paused, ensure_not_paused, fetch_program_config, ProgramConfig};
+use crate::qr::{assemble_text, render_frames_svg, render_svg, split_frames, MAX_QR_CHUNK};
+use crate::rpc::{get_account_data_base64, get_signature_status};
+use crate::solana_pay::{
+    transaction_request_link, transaction_request_url, HttpRequest, RpcBlockhashSource,
+    TransactionRequestHandler, TransactionRequestResponse,
+};
+use crate::storage::{self, DelegationDraft};
 use crate::timed_delegation::{
-    build_create_delegation_instructions, build_set_auth_instructions,
-    build_withdraw_instructions, build_withdraw_message, decode_base64,
-    derive_addresses, parse_nonce_from_state, DEFAULT_PROGRAM_ID, USDC_MINT,
+    build_create_delegation_instructions, build_memo_instruction, build_set_auth_instructions,
+    build_withdraw_instructions, build_withdraw_message, check_spending_rules, decode_base64,
+    derive_addresses, parse_amount, parse_delegation_state, DelegationState, SpendingRules, SpendingViolation,
 };
-use crate::wallet::{PasskeyEnv, WalletAdapter};
+use crate::pull_swap::{build_pull_and_swap, execute_pull_and_swap};
+use crate::swap_delegate::{self, load_pending, SwapDelegateOutcome, SwapDelegateParams};
+use crate::token_registry::{default_tokens, SOL_MINT};
+use crate::voucher::WithdrawVoucher;
+use crate::wallet::{PasskeyEnv, WalletAdapter, WalletChoice};
+use crate::wallet_context::use_wallet;
 use solana_sdk::pubkey::Pubkey;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::str::FromStr;
 
-const RPC_URL: &str = "https://johna-k3cr1v-fast-mainnet.helius-rpc.com";
+/// A transaction's blockhash lapses after about a minute; past this an
+/// unseen signature will never land.
+const PENDING_TX_EXPIRY_MS: f64 = 120_000.0;
 
 #[component]
 pub fn DelegationModal() -> Element {
-    let mut wallet = use_signal(|| WalletAdapter::new());
-    let mut wallet_address = use_signal(|| Option::<String>::None);
-    let mut mode = use_signal(|| "delegate".to_string());
-    let program_id = DEFAULT_PROGRAM_ID.to_string();
-    let mint = USDC_MINT.to_string();
-    let mut delegate_beneficiary = use_signal(|| String::new());
-    let mut delegate_amount = use_signal(|| "10".to_string());
-    let mut delegate_duration_hours = use_signal(|| "1".to_string());
+    let wallet_ctx = use_wallet();
+    let wallet = wallet_ctx.adapter;
+    let wallet_address = use_memo(move || wallet.read().get_public_key());
+    let draft = use_hook(|| storage::DRAFT.load().unwrap_or_default());
+    let mut mode = use_signal(|| draft.mode.clone());
+    let program_id = config::program_id();
+    let mint = config::usdc_mint();
+    let mut delegate_beneficiary = use_signal(|| draft.delegate_beneficiary.clone());
+    let mut delegate_amount = use_signal(|| draft.delegate_amount.clone());
+    let mut delegate_duration_hours = use_signal(|| draft.delegate_duration_hours.clone());
+    let mut delegate_max_per_withdraw = use_signal(|| draft.delegate_max_per_withdraw.clone());
+    let mut delegate_cooldown_minutes = use_signal(|| draft.delegate_cooldown_minutes.clone());
+    let mut delegate_daily_cap = use_signal(|| draft.delegate_daily_cap.clone());
     let mut status = use_signal(|| Option::<String>::None);
-    let mut withdraw_beneficiary = use_signal(|| String::new());
-    let mut withdraw_delegator = use_signal(|| String::new());
-    let mut withdraw_amount = use_signal(|| "4.2".to_string());
-    let mut auth_expiry_minutes = use_signal(|| "2".to_string());
+    let mut withdraw_beneficiary = use_signal(|| draft.withdraw_beneficiary.clone());
+    let mut withdraw_delegator = use_signal(|| draft.withdraw_delegator.clone());
+    let mut withdraw_amount = use_signal(|| draft.withdraw_amount.clone());
+    let mut auth_expiry_minutes = use_signal(|| draft.auth_expiry_minutes.clone());
     let mut delegation_status = use_signal(|| Option::<String>::None);
-    let mut passkey_pubkey_b64 = use_signal(|| String::new());
-    let mut passkey_cred_id_b64 = use_signal(|| String::new());
+    let mut passkey_pubkey_b64 = use_signal(String::new);
+    let mut passkey_cred_id_b64 = use_signal(String::new);
     let mut passkey_status = use_signal(|| Option::<String>::None);
     let mut passkey_env = use_signal(|| Option::<PasskeyEnv>::None);
+    let mut payment_request = use_signal(|| Option::<PaymentRequest>::None);
+    let mut return_url = use_signal(|| Option::<String>::None);
+    let mut request_link = use_signal(|| Option::<String>::None);
+    let mut request_qr = use_signal(|| Option::<String>::None);
+    // (link, svg) for wallets that speak Solana Pay transaction requests.
+    let mut solana_pay_qr = use_signal(|| Option::<(String, String)>::None);
+    let mut solana_pay_preview = use_signal(|| Option::<String>::None);
+    let mut voucher_qr = use_signal(Vec::<String>::new);
+    let mut voucher_text = use_signal(String::new);
+    let mut voucher_input = use_signal(String::new);
+    let mut imported_voucher = use_signal(|| Option::<WithdrawVoucher>::None);
+    let mut ledger = use_signal(|| Option::<Ledger>::None);
+    let mut ledger_loading = use_signal(|| false);
+    let mut fund_with_sol = use_signal(|| false);
+    let mut fund_sol_amount = use_signal(|| "0.1".to_string());
+    let mut pending_swap = use_signal(load_pending);
+    let mut swap_delegate_busy = use_signal(|| false);
+    let mut settle_mint = use_signal(config::usdc_mint);
+    let mut last_tx = use_signal(|| Option::<String>::None);
+    let mut show_wallet_picker = use_signal(|| false);
+    let has_aggregators = config::cluster().has_aggregators();
+    let mut program_cfg = use_signal(|| Option::<ProgramConfig>::None);
+    let mut restored_handoff = use_signal(|| Option::<HandoffState>::None);
+    let mut recent_beneficiaries = use_signal(storage::recent_beneficiaries);
+    let mut pending_txs = use_signal(|| storage::pending_txs(config::cluster()));
+
+    // Anything the page was waiting on before a reload: drop what has since
+    // landed (or failed) and keep the rest listed.
+    use_hook(move || {
+        spawn(async move {
+            let mut still_pending = Vec::new();
+            for tx in storage::pending_txs(config::cluster()) {
+                match get_signature_status(&rpc_url(), &tx.signature).await {
+                    Ok(Some(s)) if s.err.is_some() => {
+                        storage::remove_pending_tx(&tx.signature);
+                        status.set(Some(format!("Transaction {} failed", tx.signature)));
+                    }
+                    Ok(Some(s)) if matches!(s.confirmation_status.as_deref(), Some("confirmed") | Some("finalized")) => {
+                        storage::remove_pending_tx(&tx.signature);
+                        last_tx.set(Some(tx.signature.clone()));
+                    }
+                    Ok(None) if Date::now() - tx.sent_ms > PENDING_TX_EXPIRY_MS => {
+                        storage::remove_pending_tx(&tx.signature);
+                    }
+                    _ => still_pending.push(tx),
+                }
+            }
+            pending_txs.set(still_pending);
+        })
+    });
+
+    let draft_snapshot = move || DelegationDraft {
+        mode: mode.read().clone(),
+        delegate_beneficiary: delegate_beneficiary.read().clone(),
+        delegate_amount: delegate_amount.read().clone(),
+        delegate_duration_hours: delegate_duration_hours.read().clone(),
+        delegate_max_per_withdraw: delegate_max_per_withdraw.read().clone(),
+        delegate_cooldown_minutes: delegate_cooldown_minutes.read().clone(),
+        delegate_daily_cap: delegate_daily_cap.read().clone(),
+        withdraw_beneficiary: withdraw_beneficiary.read().clone(),
+        withdraw_delegator: withdraw_delegator.read().clone(),
+        withdraw_amount: withdraw_amount.read().clone(),
+        auth_expiry_minutes: auth_expiry_minutes.read().clone(),
+    };
+
+    let mut apply_draft = move |draft: &DelegationDraft| {
+        mode.set(draft.mode.clone());
+        delegate_beneficiary.set(draft.delegate_beneficiary.clone());
+        delegate_amount.set(draft.delegate_amount.clone());
+        delegate_duration_hours.set(draft.delegate_duration_hours.clone());
+        delegate_max_per_withdraw.set(draft.delegate_max_per_withdraw.clone());
+        delegate_cooldown_minutes.set(draft.delegate_cooldown_minutes.clone());
+        delegate_daily_cap.set(draft.delegate_daily_cap.clone());
+        withdraw_beneficiary.set(draft.withdraw_beneficiary.clone());
+        withdraw_delegator.set(draft.withdraw_delegator.clone());
+        withdraw_amount.set(draft.withdraw_amount.clone());
+        auth_expiry_minutes.set(draft.auth_expiry_minutes.clone());
+    };
+
+    // A payment request fixes every delegate field shown or implied by its
+    // review card: anything it leaves out falls back to the defaults rather
+    // than to whatever a saved draft held, and no spending rules apply.
+    let mut apply_payment_request = move |req: &PaymentRequest| {
+        let defaults = DelegationDraft::default();
+        mode.set("delegate".to_string());
+        delegate_beneficiary.set(req.beneficiary.clone());
+        delegate_amount.set(req.amount.clone().unwrap_or(defaults.delegate_amount));
+        delegate_duration_hours.set(
+            req.duration_hours
+                .map(|hours| hours.to_string())
+                .unwrap_or(defaults.delegate_duration_hours),
+        );
+        delegate_max_per_withdraw.set(String::new());
+        delegate_cooldown_minutes.set(String::new());
+        delegate_daily_cap.set(String::new());
+    };
+
+    // Saved on every edit; mobile browsers often reload the page while the
+    // user is away in the wallet or passkey prompt.
+    use_effect(move || storage::DRAFT.save(&draft_snapshot()));
+
+    let mut remember_beneficiary = move |address: &str| {
+        storage::remember_beneficiary(address);
+        recent_beneficiaries.set(storage::recent_beneficiaries());
+    };
+
+    let clear_saved_data = move |_| {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let confirmed = window
+            .confirm_with_message("Clear saved drafts, wallet, sign-in and history on this device? Your passkey is kept.")
+            .unwrap_or(false);
+        if confirmed {
+            storage::clear_all();
+            let _ = window.location().reload();
+        }
+    };
+
+    use_hook(|| {
+        spawn(async move {
+            let Ok(program_id) = Pubkey::from_str(&config::program_id()) else {
+                return;
+            };
+            match fetch_program_config(&rpc_url(), &program_id).await {
+                Ok(cfg) => program_cfg.set(cfg),
+                Err(e) => log::warn!("Failed to load program config: {}", e),
+            }
+        })
+    });
 
     {
         let adapter = wallet.read().clone();
-        let mut passkey_pubkey_b64 = passkey_pubkey_b64.clone();
-        let mut passkey_cred_id_b64 = passkey_cred_id_b64.clone();
-        let mut passkey_status = passkey_status.clone();
-        let mut passkey_env = passkey_env.clone();
         use_effect(move || {
             let adapter = adapter.clone();
+            let search = web_sys::window()
+                .and_then(|w| w.location().search().ok())
+                .unwrap_or_default();
+            match PaymentRequest::parse(&search) {
+                Ok(Some(req)) => {
+                    apply_payment_request(&req);
+                    payment_request.set(Some(req));
+                }
+                Ok(None) => {}
+                Err(e) => status.set(Some(format!("Invalid payment request: {}", e))),
+            }
             spawn(async move {
                 if let Ok(Some(passkey)) = adapter.get_stored_passkey().await {
                     passkey_pubkey_b64.set(passkey.pubkey_b64);
@@ -59,15 +223,31 @@ pub fn DelegationModal() -> Element {
         });
     }
 
-    let connect_wallet = move |_| {
+    let connect_wallet = move |_| show_wallet_picker.set(true);
+
+    let on_wallet_connected = move |_| {
+        show_wallet_picker.set(false);
+        status.set(None);
+    };
+
+    let disconnect_wallet = move |_| {
         spawn(async move {
-            match WalletAdapter::connect_wallet().await {
-                Ok(address) => {
-                    wallet_address.set(Some(address.clone()));
-                    status.set(None);
-                }
-                Err(e) => status.set(Some(format!("Connect failed: {}", e))),
+            match wallet_ctx.disconnect().await {
+                Ok(()) => status.set(None),
+                Err(e) => status.set(Some(format!("Disconnect failed: {}", e))),
+            }
+        });
+    };
+
+    let mut signing_in = use_signal(|| false);
+    let sign_in = move |_| {
+        spawn(async move {
+            signing_in.set(true);
+            match wallet_ctx.sign_in().await {
+                Ok(_) => status.set(Some("Signed in".to_string())),
+                Err(e) => status.set(Some(format!("Sign-in failed: {}", e))),
             }
+            signing_in.set(false);
         });
     };
 
@@ -79,14 +259,24 @@ pub fn DelegationModal() -> Element {
         let beneficiary = delegate_beneficiary.read().trim().to_string();
         let max_amount = delegate_amount.read().clone();
         let duration_hours = delegate_duration_hours.read().clone();
+        let max_per_withdraw = delegate_max_per_withdraw.read().clone();
+        let cooldown_minutes = delegate_cooldown_minutes.read().clone();
+        let daily_cap = delegate_daily_cap.read().clone();
         let passkey_pubkey_b64 = passkey_pubkey_b64.read().clone();
         let env = passkey_env.read().clone();
         let wallet_address = wallet_address.read().clone();
+        let request = payment_request.read().clone();
         spawn(async move {
             let Some(fee_payer) = wallet_address else {
                 status.set(Some("Connect wallet first".to_string()));
                 return;
             };
+            if let Some(requested_mint) = request.as_ref().and_then(|r| r.mint.as_ref()) {
+                if requested_mint.trim() != mint.trim() {
+                    status.set(Some(format!("Unsupported mint in payment request: {}", requested_mint)));
+                    return;
+                }
+            }
             if beneficiary.is_empty() {
                 status.set(Some("Enter beneficiary pubkey".to_string()));
                 return;
@@ -132,6 +322,13 @@ pub fn DelegationModal() -> Element {
                     return;
                 }
             };
+            let rules = match parse_spending_rules(&max_per_withdraw, &cooldown_minutes, &daily_cap) {
+                Ok(v) => v,
+                Err(e) => {
+                    status.set(Some(e));
+                    return;
+                }
+            };
             let duration_hours: i64 = duration_hours.parse().unwrap_or(1);
             let now = (Date::now() / 1000.0) as i64;
             let start_ts = now - 60;
@@ -145,6 +342,7 @@ pub fn DelegationModal() -> Element {
                 max_amount,
                 start_ts,
                 end_ts,
+                &rules,
             ) {
                 Ok(v) => v,
                 Err(e) => {
@@ -153,7 +351,14 @@ pub fn DelegationModal() -> Element {
                 }
             };
 
-            if let Ok(_) = get_account_data_base64(RPC_URL, &addrs.delegation_pda.to_string()).await {
+            if let Err(e) = ensure_not_paused(&rpc_url(), &program_id).await {
+                status.set(Some(e));
+                return;
+            }
+            if get_account_data_base64(&rpc_url(), &addrs.delegation_pda.to_string())
+                .await
+                .is_ok()
+            {
                 status.set(Some("Delegation already exists for this beneficiary".to_string()));
                 return;
             }
@@ -172,6 +377,9 @@ pub fn DelegationModal() -> Element {
                 }
             };
             instructions.extend(auth_ixs);
+            if let Some(memo) = request.as_ref().and_then(|r| r.memo.as_ref()) {
+                instructions.push(build_memo_instruction(&delegator, memo));
+            }
 
             let json = match to_string(&instructions) {
                 Ok(v) => v,
@@ -182,7 +390,7 @@ pub fn DelegationModal() -> Element {
             };
 
             let adapter = wallet.read().clone();
-            let sig = match adapter.send_instructions_json(RPC_URL, &fee_payer, &json).await {
+            let sig = match adapter.send_instructions_json(&rpc_url(), &fee_payer, &json).await {
                 Ok(sig) => sig,
                 Err(e) => {
                     status.set(Some(e));
@@ -191,7 +399,156 @@ pub fn DelegationModal() -> Element {
             };
             withdraw_delegator.set(delegator.to_string());
             withdraw_beneficiary.set(beneficiary.to_string());
+            remember_beneficiary(&beneficiary.to_string());
+            if let Some(req) = request {
+                return_url.set(req.return_url);
+            }
             status.set(Some(format!("Delegation + authenticator set: {}", sig)));
+            last_tx.set(Some(sig));
+        });
+    };
+
+    let program_id_for_swap = program_id.clone();
+    let mint_for_swap = mint.clone();
+    let swap_and_delegate = move |_| {
+        let program_id = program_id_for_swap.clone();
+        let mint = mint_for_swap.clone();
+        let beneficiary = delegate_beneficiary.read().trim().to_string();
+        let sol_amount = fund_sol_amount.read().clone();
+        let duration_hours = delegate_duration_hours.read().clone();
+        let max_per_withdraw = delegate_max_per_withdraw.read().clone();
+        let cooldown_minutes = delegate_cooldown_minutes.read().clone();
+        let daily_cap = delegate_daily_cap.read().clone();
+        let passkey_pubkey_b64 = passkey_pubkey_b64.read().clone();
+        let env = passkey_env.read().clone();
+        let wallet_address = wallet_address.read().clone();
+        spawn(async move {
+            let Some(fee_payer) = wallet_address else {
+                status.set(Some("Connect wallet first".to_string()));
+                return;
+            };
+            if beneficiary.is_empty() {
+                status.set(Some("Enter beneficiary pubkey".to_string()));
+                return;
+            }
+            if let Some(env) = env {
+                if env.in_app || !env.supported || !env.platform {
+                    status.set(Some(format!(
+                        "Passkeys require {} in the system browser. Tap Open in Browser.",
+                        env.hint
+                    )));
+                    return;
+                }
+            }
+            if passkey_pubkey_b64.is_empty() {
+                status.set(Some("Register a passkey before delegating".to_string()));
+                return;
+            }
+            let program_id = Pubkey::from_str(program_id.trim()).map_err(|e| e.to_string());
+            let mint = Pubkey::from_str(mint.trim()).map_err(|e| e.to_string());
+            let beneficiary = Pubkey::from_str(beneficiary.trim()).map_err(|e| e.to_string());
+            let delegator = Pubkey::from_str(fee_payer.trim()).map_err(|e| e.to_string());
+            if program_id.is_err() || mint.is_err() || beneficiary.is_err() || delegator.is_err() {
+                status.set(Some("Invalid pubkey".to_string()));
+                return;
+            }
+            let mint = mint.unwrap();
+            let auth_pubkey = match decode_base64(&passkey_pubkey_b64) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    status.set(Some(e));
+                    return;
+                }
+            };
+            let in_amount = match parse_amount(sol_amount.trim(), 9) {
+                Ok(v) if v > 0 => v,
+                Ok(_) => {
+                    status.set(Some("Enter a SOL amount".to_string()));
+                    return;
+                }
+                Err(e) => {
+                    status.set(Some(e));
+                    return;
+                }
+            };
+            let rules = match parse_spending_rules(&max_per_withdraw, &cooldown_minutes, &daily_cap) {
+                Ok(v) => v,
+                Err(e) => {
+                    status.set(Some(e));
+                    return;
+                }
+            };
+            let duration_hours: i64 = duration_hours.parse().unwrap_or(1);
+            let params = SwapDelegateParams {
+                program_id: program_id.unwrap(),
+                delegator: delegator.unwrap(),
+                beneficiary: beneficiary.unwrap(),
+                mint,
+                input_mint: SOL_MINT.to_string(),
+                in_amount,
+                slippage_bps: auto_slippage_bps(SOL_MINT, &mint.to_string()),
+                duration_secs: duration_hours * 3600,
+                rules,
+                auth_pubkey,
+            };
+
+            swap_delegate_busy.set(true);
+            let adapter = wallet.read().clone();
+            let result = swap_delegate::run(&adapter, &rpc_url(), &params, |step| status.set(Some(step))).await;
+            swap_delegate_busy.set(false);
+            pending_swap.set(load_pending());
+            match result {
+                Ok(SwapDelegateOutcome::Atomic { signature, delegated }) => {
+                    withdraw_delegator.set(params.delegator.to_string());
+                    withdraw_beneficiary.set(params.beneficiary.to_string());
+                    remember_beneficiary(&params.beneficiary.to_string());
+                    status.set(Some(format!(
+                        "Swapped and delegated {} USDC in one transaction: {}",
+                        format_amount(delegated, 6),
+                        signature
+                    )));
+                    last_tx.set(Some(signature));
+                }
+                Ok(SwapDelegateOutcome::TwoStep { swap_signature, delegation_signature, delegated }) => {
+                    withdraw_delegator.set(params.delegator.to_string());
+                    withdraw_beneficiary.set(params.beneficiary.to_string());
+                    remember_beneficiary(&params.beneficiary.to_string());
+                    status.set(Some(format!(
+                        "Swap {} then delegated {} USDC: {}",
+                        swap_signature,
+                        format_amount(delegated, 6),
+                        delegation_signature
+                    )));
+                    last_tx.set(Some(delegation_signature));
+                }
+                Err(e) => status.set(Some(format!("Swap + delegate failed: {}", e))),
+            }
+        });
+    };
+
+    let resume_swap_delegation = move |_| {
+        let Some(pending) = pending_swap.read().clone() else {
+            return;
+        };
+        spawn(async move {
+            swap_delegate_busy.set(true);
+            status.set(Some("Creating delegation from completed swap...".to_string()));
+            let adapter = wallet.read().clone();
+            match swap_delegate::resume(&adapter, &rpc_url(), &pending).await {
+                Ok(sig) => {
+                    withdraw_delegator.set(pending.delegator.clone());
+                    withdraw_beneficiary.set(pending.beneficiary.clone());
+                    status.set(Some(format!(
+                        "Delegated {} USDC: {}",
+                        format_amount(pending.received_amount, 6),
+                        sig
+                    )));
+                    last_tx.set(Some(sig));
+                }
+                Err(e) => status.set(Some(format!("Resume failed: {}", e))),
+            }
+            pending_swap.set(load_pending());
+            swap_delegate_busy.set(false);
         });
     };
 
@@ -234,12 +591,61 @@ pub fn DelegationModal() -> Element {
         });
     };
 
+    let handoff_snapshot = move || HandoffState {
+        cluster: config::cluster().as_str().to_string(),
+        draft: draft_snapshot(),
+        passkey_pubkey_b64: passkey_pubkey_b64.read().clone(),
+        passkey_cred_id_b64: passkey_cred_id_b64.read().clone(),
+        voucher: imported_voucher
+            .read()
+            .as_ref()
+            .and_then(|v| v.encode().ok())
+            .or_else(|| assemble_text(&voucher_text.read()).ok()),
+        from_wallet: handoff::detect_in_app_wallet()
+            .map(|w| w.label().to_string())
+            .or_else(|| wallet.read().wallet_name()),
+    };
+
     let open_in_browser = move |_| {
         let adapter = wallet.read().clone();
+        let url = match handoff_snapshot().url() {
+            Ok(url) => url,
+            Err(e) => {
+                status.set(Some(e));
+                return;
+            }
+        };
         spawn(async move {
-            let _ = adapter
-                .open_system_browser("https://pull.unruggable.io")
-                .await;
+            let _ = adapter.open_system_browser(&url).await;
+        });
+    };
+
+    // Back into the wallet's in-app browser with whatever was finished here
+    // (passkey, voucher), so the wallet can sign.
+    let mut return_to_wallet = move |target: MobileWallet| {
+        let url = match handoff_snapshot().url() {
+            Ok(url) => url,
+            Err(e) => {
+                status.set(Some(e));
+                return;
+            }
+        };
+        if let Some(window) = web_sys::window() {
+            let _ = window
+                .location()
+                .set_href(&target.browse_url(&url, &handoff::page_origin()));
+        }
+    };
+
+    let connect_mobile_adapter = move |_| {
+        let Some(adapter) = handoff::mobile_wallet_adapter() else {
+            return;
+        };
+        spawn(async move {
+            match wallet_ctx.connect(WalletChoice::Standard(adapter)).await {
+                Ok(_) => status.set(None),
+                Err(e) => status.set(Some(format!("Mobile Wallet Adapter: {}", e))),
+            }
         });
     };
 
@@ -308,7 +714,7 @@ pub fn DelegationModal() -> Element {
             };
 
             let adapter = wallet.read().clone();
-            let sig = match adapter.send_instructions_json(RPC_URL, &delegator_str, &json).await {
+            let sig = match adapter.send_instructions_json(&rpc_url(), &delegator_str, &json).await {
                 Ok(sig) => sig,
                 Err(e) => {
                     status.set(Some(e));
@@ -316,12 +722,13 @@ pub fn DelegationModal() -> Element {
                 }
             };
             status.set(Some(format!("Authenticator set: {}", sig)));
+            last_tx.set(Some(sig));
         });
     };
 
     let program_id_for_check = program_id.clone();
     let mint_for_check = mint.clone();
-    let check_delegation = move |_| {
+    let refresh_delegation = move || {
         let program_id = program_id_for_check.clone();
         let mint = mint_for_check.clone();
         let beneficiary = withdraw_beneficiary.read().trim().to_string();
@@ -344,12 +751,90 @@ pub fn DelegationModal() -> Element {
             let beneficiary = beneficiary.unwrap();
             let delegator = delegator.unwrap();
 
-            match fetch_nonce(&program_id, &mint, &beneficiary, &delegator).await {
-                Ok(n) => delegation_status.set(Some(format!("Delegation found (nonce {})", n))),
+            match fetch_state(&program_id, &mint, &beneficiary, &delegator).await {
+                Ok(state) => delegation_status.set(Some(format!(
+                    "Delegation found (nonce {}){}",
+                    state.nonce,
+                    describe_rules(&state.rules)
+                ))),
                 Err(e) => delegation_status.set(Some(format!("Delegation not found: {}", e))),
             }
         });
     };
+    let refresh_delegation_for_check = refresh_delegation.clone();
+    let check_delegation = move |_| refresh_delegation_for_check();
+
+    let program_id_for_history = program_id.clone();
+    let mint_for_history = mint.clone();
+    let reload_history = move || {
+        let program_id = program_id_for_history.clone();
+        let mint = mint_for_history.clone();
+        let beneficiary = withdraw_beneficiary.read().trim().to_string();
+        let delegator = withdraw_delegator.read().trim().to_string();
+        spawn(async move {
+            if beneficiary.is_empty() || delegator.is_empty() {
+                delegation_status.set(Some("Enter beneficiary + delegator pubkeys".to_string()));
+                return;
+            }
+            let program_id = Pubkey::from_str(program_id.trim()).map_err(|e| e.to_string());
+            let mint = Pubkey::from_str(mint.trim()).map_err(|e| e.to_string());
+            let beneficiary = Pubkey::from_str(beneficiary.trim()).map_err(|e| e.to_string());
+            let delegator = Pubkey::from_str(delegator.trim()).map_err(|e| e.to_string());
+            if program_id.is_err() || mint.is_err() || beneficiary.is_err() || delegator.is_err() {
+                delegation_status.set(Some("Invalid pubkey".to_string()));
+                return;
+            }
+            let program_id = program_id.unwrap();
+            let mint = mint.unwrap();
+            let beneficiary = beneficiary.unwrap();
+            let delegator = delegator.unwrap();
+
+            ledger_loading.set(true);
+            match fetch_ledger(&rpc_url(), &program_id, &delegator, &beneficiary, &mint, 6).await {
+                Ok(l) => ledger.set(Some(l)),
+                Err(e) => delegation_status.set(Some(format!("History unavailable: {}", e))),
+            }
+            ledger_loading.set(false);
+        });
+    };
+    let reload_history_for_load = reload_history.clone();
+    let load_history = move |_| reload_history_for_load();
+
+    // Follow wallet account switches: in withdraw mode the beneficiary is the
+    // connected wallet, so it follows the switch. Counterparty fields (the
+    // delegator, or the beneficiary being delegated to) are left alone. Then
+    // reload whatever was on screen for the old account.
+    let previous_address = use_hook(|| Rc::new(RefCell::new(Option::<String>::None)));
+    {
+        let refresh_delegation = refresh_delegation.clone();
+        let reload_history = reload_history.clone();
+        use_effect(move || {
+            let current = wallet_address.read().clone();
+            let previous = previous_address.replace(current.clone());
+            let (Some(old), Some(new)) = (previous, current) else {
+                return;
+            };
+            if old == new {
+                return;
+            }
+            if mode.peek().as_str() == "withdraw" && *withdraw_beneficiary.peek() == old {
+                withdraw_beneficiary.set(new.clone());
+            }
+            imported_voucher.set(None);
+            last_tx.set(None);
+            status.set(Some(format!(
+                "Switched to {}...",
+                new.chars().take(8).collect::<String>()
+            )));
+            if delegation_status.peek().is_some() {
+                refresh_delegation();
+            }
+            if ledger.peek().is_some() {
+                ledger.set(None);
+                reload_history();
+            }
+        });
+    }
 
     let program_id_for_withdraw = program_id.clone();
     let mint_for_withdraw = mint.clone();
@@ -364,6 +849,8 @@ pub fn DelegationModal() -> Element {
         let passkey_cred_id_b64 = passkey_cred_id_b64.read().clone();
         let wallet_address = wallet_address.read().clone();
         let env = passkey_env.read().clone();
+        let voucher = imported_voucher.read().clone();
+        let settle_mint = settle_mint.read().clone();
         spawn(async move {
             let Some(beneficiary_wallet) = wallet_address else {
                 status.set(Some("Connect beneficiary wallet first".to_string()));
@@ -382,19 +869,6 @@ pub fn DelegationModal() -> Element {
                 status.set(Some("Enter delegator pubkey".to_string()));
                 return;
             }
-            if passkey_pubkey_b64.is_empty() || passkey_cred_id_b64.is_empty() {
-                status.set(Some("Register/load a passkey first".to_string()));
-                return;
-            }
-            if let Some(env) = env {
-                if env.in_app || !env.supported || !env.platform {
-                    status.set(Some(format!(
-                        "Passkeys require {} in the system browser. Tap Open in Browser.",
-                        env.hint
-                    )));
-                    return;
-                }
-            }
 
             let program_id = Pubkey::from_str(program_id.trim()).map_err(|e| e.to_string());
             let mint = Pubkey::from_str(mint.trim()).map_err(|e| e.to_string());
@@ -409,13 +883,18 @@ pub fn DelegationModal() -> Element {
             let beneficiary = beneficiary.unwrap();
             let delegator = delegator.unwrap();
 
-            let nonce_u64 = match fetch_nonce(&program_id, &mint, &beneficiary, &delegator).await {
-                Ok(n) => n,
+            let program_cfg = match fetch_program_config(&rpc_url(), &program_id).await {
+                Ok(v) => v,
                 Err(e) => {
-                    status.set(Some(format!("Delegation not found or invalid: {}", e)));
+                    status.set(Some(e));
                     return;
                 }
             };
+            if let Err(e) = check_not_paused(program_cfg.as_ref()) {
+                status.set(Some(e));
+                return;
+            }
+            let fee_recipient = program_cfg.as_ref().and_then(|c| c.charged_fee_recipient());
 
             let amount = match parse_amount(&withdraw_amount, 6) {
                 Ok(v) => v,
@@ -424,105 +903,391 @@ pub fn DelegationModal() -> Element {
                     return;
                 }
             };
-            let expiry_minutes: i64 = auth_expiry_minutes.parse().unwrap_or(2);
-            let now = (Date::now() / 1000.0) as i64;
-            let auth_expiry_ts = now + expiry_minutes * 60;
-
-            let addrs = derive_addresses(&program_id, &delegator, &beneficiary, &mint);
-            let message = build_withdraw_message(
-                &program_id,
-                &addrs.delegation_pda,
-                &addrs.source_ata,
-                &addrs.destination_ata,
-                amount,
-                nonce_u64,
-                auth_expiry_ts,
-            );
-            let mut hasher = Sha256::new();
-            hasher.update(&message);
-            let challenge = hasher.finalize();
-            let challenge_b64 = B64.encode(challenge);
 
             let adapter = wallet.read().clone();
-            let passkey_sig = match adapter
-                .sign_passkey(&challenge_b64, &passkey_cred_id_b64)
-                .await
-            {
-                Ok(sig) => sig,
-                Err(e) => {
-                    status.set(Some(e));
-                    return;
+            let voucher = match voucher {
+                Some(v) => {
+                    if v.delegator != delegator.to_string()
+                        || v.beneficiary != beneficiary.to_string()
+                        || v.mint != mint.to_string()
+                    {
+                        status.set(Some("Imported voucher does not match this delegation".to_string()));
+                        return;
+                    }
+                    let now = (Date::now() / 1000.0) as i64;
+                    if v.is_expired(now) {
+                        status.set(Some("Imported voucher has expired".to_string()));
+                        return;
+                    }
+                    let state = match fetch_state(&program_id, &mint, &beneficiary, &delegator).await {
+                        Ok(state) => state,
+                        Err(e) => {
+                            status.set(Some(format!("Delegation not found or invalid: {}", e)));
+                            return;
+                        }
+                    };
+                    if let Err(e) = v
+                        .check_against(amount, state.nonce)
+                        .and_then(|_| check_withdraw_allowed(&state, amount, now))
+                    {
+                        status.set(Some(format!("Imported voucher rejected: {}", e)));
+                        return;
+                    }
+                    v
+                }
+                None => {
+                    if passkey_pubkey_b64.is_empty() || passkey_cred_id_b64.is_empty() {
+                        status.set(Some("Register/load a passkey first".to_string()));
+                        return;
+                    }
+                    if let Some(env) = env {
+                        if env.in_app || !env.supported || !env.platform {
+                            status.set(Some(format!(
+                                "Passkeys require {} in the system browser. Tap Open in Browser.",
+                                env.hint
+                            )));
+                            return;
+                        }
+                    }
+                    let expiry_minutes: i64 = auth_expiry_minutes.parse().unwrap_or(2);
+                    match sign_voucher(
+                        &adapter,
+                        &program_id,
+                        &mint,
+                        &delegator,
+                        &beneficiary,
+                        amount,
+                        expiry_minutes,
+                        &passkey_pubkey_b64,
+                        &passkey_cred_id_b64,
+                    )
+                    .await
+                    {
+                        Ok(v) => v,
+                        Err(e) => {
+                            status.set(Some(e));
+                            return;
+                        }
+                    }
                 }
             };
 
-            let signature = match decode_base64(&passkey_sig.signature_b64) {
+            let (_, instructions) = match build_withdraw_instructions(
+                &program_id,
+                &delegator,
+                &beneficiary,
+                &mint,
+                voucher.amount,
+                voucher.nonce,
+                voucher.auth_expiry_ts,
+                &voucher.auth_pubkey,
+                &voucher.authenticator_data,
+                &voucher.client_data_json,
+                &voucher.signature,
+                fee_recipient.as_ref(),
+            ) {
                 Ok(v) => v,
                 Err(e) => {
                     status.set(Some(e));
                     return;
                 }
             };
-            let authenticator_data = match decode_base64(&passkey_sig.authenticator_data_b64) {
+
+            if settle_mint != mint.to_string() {
+                // Only what reaches the beneficiary after the protocol fee is swapped.
+                let swap_amount = program_cfg
+                    .as_ref()
+                    .map(|c| c.fee_breakdown(voucher.amount).net)
+                    .unwrap_or(voucher.amount);
+                status.set(Some("Fetching a fresh quote for the swap...".to_string()));
+                let plan = match build_pull_and_swap(
+                    &rpc_url(),
+                    &beneficiary,
+                    &instructions,
+                    &mint.to_string(),
+                    &settle_mint,
+                    swap_amount,
+                    auto_slippage_bps(&mint.to_string(), &settle_mint),
+                )
+                .await
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        status.set(Some(format!("Pull-and-swap unavailable: {}", e)));
+                        return;
+                    }
+                };
+                let symbol = default_tokens()
+                    .into_iter()
+                    .find(|t| t.mint == settle_mint)
+                    .map(|t| (t.symbol, t.decimals))
+                    .unwrap_or(("tokens".to_string(), 0));
+                status.set(Some(format!(
+                    "Withdrawing and swapping, receiving at least {} {}",
+                    format_amount(plan.quote.other_amount_threshold, symbol.1),
+                    symbol.0
+                )));
+                match execute_pull_and_swap(&adapter, &rpc_url(), &beneficiary, &settle_mint, &plan).await {
+                    Ok(result) => {
+                        imported_voucher.set(None);
+                        let received = result
+                            .realized_out
+                            .map(|v| format!("{} {}", format_amount(v, symbol.1), symbol.0))
+                            .unwrap_or_else(|| "unknown amount".to_string());
+                        status.set(Some(format!("Withdraw + swap sent ({}): {}", received, result.signature)));
+                        last_tx.set(Some(result.signature));
+                    }
+                    Err(e) => status.set(Some(e)),
+                }
+                return;
+            }
+
+            let json = match to_string(&instructions) {
                 Ok(v) => v,
                 Err(e) => {
-                    status.set(Some(e));
+                    status.set(Some(e.to_string()));
                     return;
                 }
             };
-            let client_data_json = match decode_base64(&passkey_sig.client_data_json_b64) {
-                Ok(v) => v,
+
+            let sig = match adapter
+                .send_instructions_json(&rpc_url(), &beneficiary_wallet, &json)
+                .await
+            {
+                Ok(sig) => sig,
                 Err(e) => {
                     status.set(Some(e));
                     return;
                 }
             };
-            let auth_pubkey = match decode_base64(&passkey_pubkey_b64) {
+            imported_voucher.set(None);
+            status.set(Some(format!("Withdraw sent: {}", sig)));
+            last_tx.set(Some(sig));
+        });
+    };
+
+    let program_id_for_voucher = program_id.clone();
+    let mint_for_voucher = mint.clone();
+    let create_voucher = move |_| {
+        let program_id = program_id_for_voucher.clone();
+        let mint = mint_for_voucher.clone();
+        let beneficiary = withdraw_beneficiary.read().trim().to_string();
+        let delegator = withdraw_delegator.read().trim().to_string();
+        let withdraw_amount = withdraw_amount.read().clone();
+        let auth_expiry_minutes = auth_expiry_minutes.read().clone();
+        let passkey_pubkey_b64 = passkey_pubkey_b64.read().clone();
+        let passkey_cred_id_b64 = passkey_cred_id_b64.read().clone();
+        let env = passkey_env.read().clone();
+        spawn(async move {
+            if beneficiary.is_empty() || delegator.is_empty() {
+                status.set(Some("Enter beneficiary + delegator pubkeys".to_string()));
+                return;
+            }
+            if passkey_pubkey_b64.is_empty() || passkey_cred_id_b64.is_empty() {
+                status.set(Some("Register/load a passkey first".to_string()));
+                return;
+            }
+            if let Some(env) = env {
+                if env.in_app || !env.supported || !env.platform {
+                    status.set(Some(format!(
+                        "Passkeys require {} in the system browser. Tap Open in Browser.",
+                        env.hint
+                    )));
+                    return;
+                }
+            }
+            let program_id = Pubkey::from_str(program_id.trim()).map_err(|e| e.to_string());
+            let mint = Pubkey::from_str(mint.trim()).map_err(|e| e.to_string());
+            let beneficiary = Pubkey::from_str(beneficiary.trim()).map_err(|e| e.to_string());
+            let delegator = Pubkey::from_str(delegator.trim()).map_err(|e| e.to_string());
+            if program_id.is_err() || mint.is_err() || beneficiary.is_err() || delegator.is_err() {
+                status.set(Some("Invalid pubkey".to_string()));
+                return;
+            }
+            let program_id = program_id.unwrap();
+            let mint = mint.unwrap();
+            let beneficiary = beneficiary.unwrap();
+            let delegator = delegator.unwrap();
+
+            if let Err(e) = ensure_not_paused(&rpc_url(), &program_id).await {
+                status.set(Some(e));
+                return;
+            }
+
+            let amount = match parse_amount(&withdraw_amount, 6) {
                 Ok(v) => v,
                 Err(e) => {
                     status.set(Some(e));
                     return;
                 }
             };
-
-            let (_, instructions) = match build_withdraw_instructions(
+            let expiry_minutes: i64 = auth_expiry_minutes.parse().unwrap_or(2);
+            let adapter = wallet.read().clone();
+            let voucher = match sign_voucher(
+                &adapter,
                 &program_id,
+                &mint,
                 &delegator,
                 &beneficiary,
-                &mint,
                 amount,
-                nonce_u64,
-                auth_expiry_ts,
-                &auth_pubkey,
-                &authenticator_data,
-                &client_data_json,
-                &signature,
-            ) {
+                expiry_minutes,
+                &passkey_pubkey_b64,
+                &passkey_cred_id_b64,
+            )
+            .await
+            {
                 Ok(v) => v,
                 Err(e) => {
                     status.set(Some(e));
                     return;
                 }
             };
-
-            let json = match to_string(&instructions) {
+            let encoded = match voucher.encode() {
                 Ok(v) => v,
                 Err(e) => {
-                    status.set(Some(e.to_string()));
+                    status.set(Some(e));
                     return;
                 }
             };
-
-            let sig = match adapter
-                .send_instructions_json(RPC_URL, &beneficiary_wallet, &json)
-                .await
-            {
-                Ok(sig) => sig,
+            match render_frames_svg(&encoded) {
+                Ok(svgs) => voucher_qr.set(svgs),
                 Err(e) => {
                     status.set(Some(e));
                     return;
                 }
+            }
+            voucher_text.set(split_frames(&encoded, MAX_QR_CHUNK).join("\n"));
+            status.set(Some(format!(
+                "Voucher valid until {}",
+                format_ts(voucher.auth_expiry_ts)
+            )));
+        });
+    };
+
+    let mut apply_voucher_text = move |text: String| match assemble_text(&text)
+        .and_then(|payload| WithdrawVoucher::decode(&payload))
+    {
+        Ok(v) => {
+            withdraw_delegator.set(v.delegator.clone());
+            withdraw_beneficiary.set(v.beneficiary.clone());
+            withdraw_amount.set(format_amount(v.amount, 6));
+            status.set(Some(format!(
+                "Voucher imported: {} USDC, expires {}",
+                format_amount(v.amount, 6),
+                format_ts(v.auth_expiry_ts)
+            )));
+            imported_voucher.set(Some(v));
+        }
+        Err(e) => status.set(Some(format!("Voucher import failed: {}", e))),
+    };
+
+    let import_voucher = move |_| {
+        let text = voucher_input.read().clone();
+        apply_voucher_text(text);
+    };
+
+    // Declared after the payment-request effect so fields carried over from
+    // the other browser win over the link's defaults, except while a payment
+    // request is under review: its fields always come from the link.
+    use_effect(move || {
+        let Some(state) = handoff::take() else {
+            return;
+        };
+        apply_draft(&state.draft);
+        if let Some(req) = payment_request.peek().clone() {
+            apply_payment_request(&req);
+        }
+        if !state.passkey_pubkey_b64.is_empty() {
+            passkey_pubkey_b64.set(state.passkey_pubkey_b64.clone());
+            passkey_cred_id_b64.set(state.passkey_cred_id_b64.clone());
+            passkey_status.set(Some("Passkey carried over from the other browser".to_string()));
+        }
+        status.set(Some("Restored your progress from the other browser".to_string()));
+        if let Some(voucher) = &state.voucher {
+            apply_voucher_text(voucher.clone());
+        }
+        restored_handoff.set(Some(state));
+    });
+
+    let scan_voucher_image = move |_| {
+        let adapter = wallet.read().clone();
+        spawn(async move {
+            match adapter.scan_qr_image("voucher-image").await {
+                Ok(codes) => apply_voucher_text(codes.join("\n")),
+                Err(e) => status.set(Some(e)),
+            }
+        });
+    };
+
+    let share_request = move |_| {
+        let req = PaymentRequest {
+            amount: Some(delegate_amount.read().trim().to_string()),
+            duration_hours: delegate_duration_hours.read().trim().parse().ok(),
+            ..PaymentRequest::new(delegate_beneficiary.read().trim())
+        };
+        if let Err(e) = req.validate() {
+            status.set(Some(e));
+            return;
+        }
+        let origin = web_sys::window()
+            .and_then(|w| w.location().origin().ok())
+            .unwrap_or_else(|| "https://pull.unruggable.io".to_string());
+        let link = req.to_url(&origin);
+        match render_svg(&link) {
+            Ok(svg) => {
+                request_qr.set(Some(svg));
+                request_link.set(Some(link));
+            }
+            Err(e) => status.set(Some(e)),
+        }
+
+        solana_pay_qr.set(None);
+        solana_pay_preview.set(None);
+        let Some(endpoint) = config::endpoints().transaction_request_url else {
+            return;
+        };
+        let auth = passkey_pubkey_b64.read().clone();
+        let auth = (!auth.is_empty()).then_some(auth);
+        let pay_link = transaction_request_link(&endpoint, &req, auth.as_deref());
+        match render_svg(&pay_link) {
+            Ok(svg) => solana_pay_qr.set(Some((pay_link, svg))),
+            Err(e) => status.set(Some(e)),
+        }
+
+        // Run the endpoint's own handler locally with the connected wallet as
+        // a stand-in payer, so a broken request shows up before it is shared.
+        let Some(account) = wallet_address.read().clone() else {
+            return;
+        };
+        let url = transaction_request_url(&endpoint, &req, auth.as_deref());
+        spawn(async move {
+            let (Ok(program_id), Ok(mint)) = (
+                Pubkey::from_str(&config::program_id()),
+                Pubkey::from_str(&config::usdc_mint()),
+            ) else {
+                return;
+            };
+            let handler = TransactionRequestHandler::new(
+                program_id,
+                mint,
+                6,
+                "Timed delegation",
+                &format!("{}/favicon.ico", origin),
+                RpcBlockhashSource { rpc_url: rpc_url() },
+            );
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                url,
+                body: Some(serde_json::json!({ "account": account }).to_string()),
+            };
+            let response = handler.handle(&request, (Date::now() / 1000.0) as i64).await;
+            let preview = match serde_json::from_str::<TransactionRequestResponse>(&response.body) {
+                Ok(body) if response.status == 200 => {
+                    format!("Wallets will show: {}", body.message.unwrap_or_default())
+                }
+                _ => format!("Transaction request failed ({}): {}", response.status, response.body),
             };
-            status.set(Some(format!("Withdraw sent: {}", sig)));
+            solana_pay_preview.set(Some(preview));
         });
     };
 
@@ -531,6 +1296,23 @@ pub fn DelegationModal() -> Element {
         let duration_hours: i64 = delegate_duration_hours.read().parse().unwrap_or(1);
         now + duration_hours * 3600
     };
+    let wallet_label = wallet
+        .read()
+        .wallet_name()
+        .map(|name| format!(" to {}", name))
+        .unwrap_or_default();
+    let in_app = passkey_env.read().as_ref().is_some_and(|env| env.in_app);
+    let handoff_from = restored_handoff
+        .read()
+        .as_ref()
+        .map(|s| s.from_wallet.as_deref().and_then(MobileWallet::from_name));
+    let has_mobile_adapter = handoff::mobile_wallet_adapter().is_some();
+    let session_expires = wallet_ctx.active_session().map(|s| format_ts(s.expires_at));
+    let fee_bps = program_cfg.read().as_ref().map(|c| c.fee_bps).unwrap_or(0);
+    let withdraw_fees = match (program_cfg.read().as_ref(), parse_amount(&withdraw_amount.read(), 6)) {
+        (Some(cfg), Ok(gross)) if cfg.fee_bps > 0 => Some(cfg.fee_breakdown(gross)),
+        _ => None,
+    };
     let auth_expires_ts = {
         let now = (Date::now() / 1000.0) as i64;
         let expiry_minutes: i64 = auth_expiry_minutes.read().parse().unwrap_or(2);
@@ -542,7 +1324,12 @@ pub fn DelegationModal() -> Element {
             style: "max-width: 560px; margin: 0 auto; padding: 24px; background: linear-gradient(135deg, #1e293b 0%, #0f172a 100%); border-radius: 16px; box-shadow: 0 8px 32px rgba(0,0,0,0.4); border: 2px solid #334155;",
             h2 { style: "color: #e0e0e0; margin-bottom: 16px; font-size: 22px; text-align: center;", "Timed Delegation" }
 
-            if wallet_address.read().is_none() {
+            if *show_wallet_picker.read() {
+                WalletPicker {
+                    on_connected: on_wallet_connected,
+                    on_close: move |_| show_wallet_picker.set(false),
+                }
+            } else if wallet_address.read().is_none() {
                 button {
                     onclick: connect_wallet,
                     style: "width: 100%; background: #0f172a; color: #e0e0e0; padding: 12px; border-radius: 12px; border: 2px solid #3b82f6; font-size: 14px; font-weight: 600;",
@@ -550,7 +1337,48 @@ pub fn DelegationModal() -> Element {
                 }
             } else {
                 div { style: "color: #94a3b8; margin-bottom: 16px; font-size: 12px; text-align: center;", 
-                    "Connected: {wallet_address.read().as_ref().unwrap().chars().take(8).collect::<String>()}..."
+                    "Connected{wallet_label}: {wallet_address.read().as_ref().unwrap().chars().take(8).collect::<String>()}... "
+                    button {
+                        onclick: disconnect_wallet,
+                        style: "background: transparent; color: #60a5fa; border: none; cursor: pointer; font-size: 12px;",
+                        "Disconnect"
+                    }
+                    if let Some(expires) = session_expires {
+                        div { style: "color: #a7f3d0; margin-top: 4px;",
+                            "Signed in until {expires} "
+                            button {
+                                onclick: move |_| wallet_ctx.sign_out(),
+                                style: "background: transparent; color: #60a5fa; border: none; cursor: pointer; font-size: 12px;",
+                                "Sign out"
+                            }
+                        }
+                    } else {
+                        button {
+                            onclick: sign_in,
+                            disabled: *signing_in.read(),
+                            style: "background: transparent; color: #60a5fa; border: none; cursor: pointer; font-size: 12px;",
+                            if *signing_in.read() { "Signing in..." } else { "Sign in with Solana" }
+                        }
+                    }
+                }
+            }
+
+            if let (Some(origin), false) = (handoff_from, in_app) {
+                div { style: "padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155; display: grid; gap: 8px; margin-bottom: 16px; font-size: 12px; color: #cbd5f5;",
+                    "Finished the passkey step? Continue in your wallet to sign."
+                    div { style: "display: flex; flex-wrap: wrap; gap: 8px;",
+                        if has_mobile_adapter {
+                            button { onclick: connect_mobile_adapter, style: "flex: 1; padding: 10px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none;", "Mobile Wallet Adapter" }
+                        }
+                        for target in MobileWallet::ALL {
+                            button {
+                                key: "{target.label()}",
+                                onclick: move |_| return_to_wallet(target),
+                                style: if Some(target) == origin { "flex: 1; padding: 10px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none;" } else { "flex: 1; padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;" },
+                                "Open in {target.label()}"
+                            }
+                        }
+                    }
                 }
             }
 
@@ -567,17 +1395,110 @@ pub fn DelegationModal() -> Element {
                     style: if mode.read().as_str() == "withdraw" { "flex:1;padding:10px;border-radius:10px;background:#10b981;color:#fff;border:none;" } else { "flex:1;padding:10px;border-radius:10px;background:#0f172a;color:#94a3b8;border:1px solid #334155;" },
                     "Withdraw"
                 }
+                button {
+                    onclick: move |_| mode.set("admin".to_string()),
+                    style: if mode.read().as_str() == "admin" { "padding:10px 14px;border-radius:10px;background:#475569;color:#fff;border:none;" } else { "padding:10px 14px;border-radius:10px;background:#0f172a;color:#94a3b8;border:1px solid #334155;" },
+                    "Admin"
+                }
+            }
+
+            if mode.read().as_str() == "admin" {
+                AdminPanel {}
             }
 
             if mode.read().as_str() == "delegate" {
                 div { style: "display: grid; gap: 8px; margin-bottom: 16px;",
-                    input { value: "{delegate_beneficiary}", oninput: move |e| delegate_beneficiary.set(e.value().clone()), placeholder: "Beneficiary Pubkey", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
-                    input { value: "{delegate_amount}", oninput: move |e| delegate_amount.set(e.value().clone()), placeholder: "USDC amount (e.g. 10)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
-                    input { value: "{delegate_duration_hours}", oninput: move |e| delegate_duration_hours.set(e.value().clone()), placeholder: "Time limit (hours)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                    if let Some(req) = payment_request.read().as_ref() {
+                        div { style: "padding: 12px; border-radius: 12px; background: #0b1220; border: 1px solid #3b82f6; display: grid; gap: 6px; font-size: 12px; color: #cbd5f5;",
+                            div { style: "font-size: 14px; font-weight: 600; color: #e0e0e0;",
+                                "Payment request from {req.label.as_deref().unwrap_or(\"merchant\")}"
+                            }
+                            div { "Beneficiary: {req.beneficiary}" }
+                            div { "Allowance: {delegate_amount} USDC over {delegate_duration_hours} hours" }
+                            if let Some(memo) = req.memo.as_ref() {
+                                div { "Memo: {memo}" }
+                            }
+                            button { onclick: move |_| payment_request.set(None), style: "padding: 8px; border-radius: 10px; background: #0f172a; color: #94a3b8; border: 1px solid #334155;", "Edit details" }
+                        }
+                    } else {
+                        input { value: "{delegate_beneficiary}", oninput: move |e| delegate_beneficiary.set(e.value().clone()), placeholder: "Beneficiary Pubkey", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                        if !recent_beneficiaries.read().is_empty() {
+                            div { style: "display: flex; flex-wrap: wrap; gap: 6px;",
+                                for address in recent_beneficiaries.read().iter().cloned() {
+                                    button {
+                                        key: "{address}",
+                                        onclick: {
+                                            let address = address.clone();
+                                            move |_| delegate_beneficiary.set(address.clone())
+                                        },
+                                        style: "background: #1e293b; color: #e0e0e0; border: 1px solid #334155; border-radius: 999px; padding: 2px 10px; font-size: 12px; cursor: pointer;",
+                                        "{short_address(&address)}"
+                                    }
+                                }
+                            }
+                        }
+                        if has_aggregators {
+                            label { style: "display: flex; align-items: center; gap: 8px; font-size: 12px; color: #94a3b8;",
+                                input { r#type: "checkbox", checked: *fund_with_sol.read(), onchange: move |e| fund_with_sol.set(e.checked()) }
+                                "Fund with SOL (swap to USDC, then delegate what you receive)"
+                            }
+                        } else {
+                            div { style: "font-size: 11px; color: #64748b;", "Funding with SOL needs swap aggregators, which are mainnet only." }
+                        }
+                        if *fund_with_sol.read() {
+                            input { value: "{fund_sol_amount}", oninput: move |e| fund_sol_amount.set(e.value().clone()), placeholder: "SOL to swap (e.g. 0.1)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                        } else {
+                            input { value: "{delegate_amount}", oninput: move |e| delegate_amount.set(e.value().clone()), placeholder: "USDC amount (e.g. 10)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                        }
+                        input { value: "{delegate_duration_hours}", oninput: move |e| delegate_duration_hours.set(e.value().clone()), placeholder: "Time limit (hours)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                        div { style: "display: flex; gap: 8px;",
+                            input { value: "{delegate_max_per_withdraw}", oninput: move |e| delegate_max_per_withdraw.set(e.value().clone()), placeholder: "Max per pull (optional)", style: "flex: 1; min-width: 0; padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                            input { value: "{delegate_cooldown_minutes}", oninput: move |e| delegate_cooldown_minutes.set(e.value().clone()), placeholder: "Cooldown (min)", style: "flex: 1; min-width: 0; padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                            input { value: "{delegate_daily_cap}", oninput: move |e| delegate_daily_cap.set(e.value().clone()), placeholder: "Daily cap (optional)", style: "flex: 1; min-width: 0; padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                        }
+                        button { onclick: share_request, style: "padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Share as Payment Request" }
+                        if let Some(svg) = request_qr.read().as_ref() {
+                            div { style: "display: grid; gap: 6px; justify-items: center; padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155;",
+                                div { style: "background: #fff; padding: 8px; border-radius: 8px;", dangerous_inner_html: "{svg}" }
+                                if let Some(link) = request_link.read().as_ref() {
+                                    div { style: "font-size: 11px; color: #64748b; word-break: break-all;", "{link}" }
+                                }
+                            }
+                        }
+                        if let Some((link, svg)) = solana_pay_qr.read().as_ref() {
+                            div { style: "display: grid; gap: 6px; justify-items: center; padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155;",
+                                div { style: "font-size: 12px; color: #cbd5f5;", "Solana Pay: scan from any wallet" }
+                                div { style: "background: #fff; padding: 8px; border-radius: 8px;", dangerous_inner_html: "{svg}" }
+                                div { style: "font-size: 11px; color: #64748b; word-break: break-all;", "{link}" }
+                                if let Some(preview) = solana_pay_preview.read().as_ref() {
+                                    div { style: "font-size: 11px; color: #94a3b8;", "{preview}" }
+                                }
+                            }
+                        }
+                    }
                     div { style: "padding: 8px 10px; border-radius: 10px; background: #0b1220; border: 1px solid #334155; color: #94a3b8; font-size: 12px;",
                         "Ends: {format_ts(delegate_end_ts)}"
                     }
-                    button { onclick: create_delegation, style: "padding: 14px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none; font-weight: 600;", "Delegate USDC" }
+                    if let Some(pending) = pending_swap.read().as_ref() {
+                        div { style: "padding: 10px; border-radius: 12px; background: #111827; border: 1px solid #f59e0b; display: grid; gap: 6px; font-size: 12px; color: #fde68a;",
+                            div { "A swap landed but its delegation was not created: {format_amount(pending.received_amount, 6)} USDC for {pending.beneficiary.chars().take(8).collect::<String>()}..." }
+                            div { style: "color: #94a3b8; word-break: break-all;", "Swap: {pending.swap_signature}" }
+                            div { style: "display: flex; gap: 8px;",
+                                button { disabled: *swap_delegate_busy.read(), onclick: resume_swap_delegation, style: "flex: 1; padding: 8px; border-radius: 10px; background: #f59e0b; color: #111827; border: none; font-weight: 600;", "Resume delegation" }
+                                button { onclick: move |_| {
+                                    swap_delegate::clear_pending();
+                                    pending_swap.set(None);
+                                }, style: "padding: 8px; border-radius: 10px; background: #0f172a; color: #94a3b8; border: 1px solid #334155;", "Dismiss" }
+                            }
+                        }
+                    }
+                    if *fund_with_sol.read() {
+                        button { disabled: *swap_delegate_busy.read(), onclick: swap_and_delegate, style: "padding: 14px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none; font-weight: 600;",
+                            if *swap_delegate_busy.read() { "Working..." } else { "Swap SOL & Delegate USDC" }
+                        }
+                    } else {
+                        button { onclick: create_delegation, style: "padding: 14px; border-radius: 10px; background: #1d4ed8; color: #fff; border: none; font-weight: 600;", "Delegate USDC" }
+                    }
                     div { style: "padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155; display: grid; gap: 8px;",
                         div { style: "font-size: 12px; color: #94a3b8;", "Passkey is required and is set automatically when you delegate." }
                         if let Some(env) = passkey_env.read().as_ref() {
@@ -633,6 +1554,31 @@ pub fn DelegationModal() -> Element {
                     input { value: "{withdraw_beneficiary}", oninput: move |e| withdraw_beneficiary.set(e.value().clone()), placeholder: "Beneficiary Pubkey (must match connected wallet)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
                     input { value: "{withdraw_delegator}", oninput: move |e| withdraw_delegator.set(e.value().clone()), placeholder: "Delegator Pubkey", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
                     input { value: "{withdraw_amount}", oninput: move |e| withdraw_amount.set(e.value().clone()), placeholder: "Withdraw amount (USDC)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
+                    if let Some(fees) = withdraw_fees {
+                        div { style: "padding: 8px 10px; border-radius: 10px; background: #0b1220; border: 1px solid #334155; color: #94a3b8; font-size: 12px; display: grid; gap: 2px;",
+                            div { "Gross: {format_amount(fees.gross, 6)} USDC" }
+                            div { "Protocol fee ({fee_bps} bps): {format_amount(fees.fee, 6)} USDC" }
+                            div { style: "color: #a7f3d0; font-weight: 600;", "You receive: {format_amount(fees.net, 6)} USDC" }
+                        }
+                    }
+                    if has_aggregators {
+                        label { style: "display: flex; align-items: center; gap: 8px; font-size: 12px; color: #94a3b8;",
+                            "Receive as"
+                            select {
+                                value: "{settle_mint}",
+                                onchange: move |e| settle_mint.set(e.value()),
+                                style: "flex: 1; padding: 8px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;",
+                                for token in default_tokens() {
+                                    option { key: "{token.mint}", value: "{token.mint}", selected: *settle_mint.read() == token.mint, "{token.symbol}" }
+                                }
+                            }
+                        }
+                    } else {
+                        div { style: "font-size: 11px; color: #64748b;", "Receiving another token needs swap aggregators, which are mainnet only." }
+                    }
+                    if *settle_mint.read() != config::usdc_mint() {
+                        div { style: "font-size: 11px; color: #64748b;", "The pulled USDC is swapped in the same transaction; minimum output is set from a fresh quote." }
+                    }
                     input { value: "{auth_expiry_minutes}", oninput: move |e| auth_expiry_minutes.set(e.value().clone()), placeholder: "Auth expiry (minutes)", style: "padding: 12px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0;" }
                     div { style: "padding: 8px 10px; border-radius: 10px; background: #0b1220; border: 1px solid #334155; color: #94a3b8; font-size: 12px;",
                         "Auth expires: {format_ts(auth_expires_ts)}"
@@ -646,58 +1592,232 @@ pub fn DelegationModal() -> Element {
                         button { onclick: check_delegation, style: "flex: 1; padding: 12px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Check Delegation" }
                         button { onclick: withdraw, style: "flex: 1; padding: 12px; border-radius: 10px; background: #10b981; color: #fff; border: none;", "Withdraw" }
                     }
+                    button { onclick: load_history, style: "padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;",
+                        if *ledger_loading.read() { "Loading history..." } else { "Load History" }
+                    }
+                    if let Some(l) = ledger.read().as_ref() {
+                        LedgerPanel { ledger: l.clone() }
+                    }
+                    div { style: "padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155; display: grid; gap: 8px;",
+                        div { style: "font-size: 12px; color: #94a3b8;", "Vouchers let the delegator sign a withdraw with their passkey and hand it to the beneficiary as a QR." }
+                        button { onclick: create_voucher, style: "padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Create Voucher" }
+                        if !voucher_qr.read().is_empty() {
+                            div { style: "display: flex; flex-wrap: wrap; gap: 8px; justify-content: center;",
+                                for (i, svg) in voucher_qr.read().iter().enumerate() {
+                                    div { key: "{i}", style: "display: grid; gap: 4px; justify-items: center;",
+                                        div { style: "background: #fff; padding: 8px; border-radius: 8px;", dangerous_inner_html: "{svg}" }
+                                        div { style: "font-size: 11px; color: #64748b;", "Part {i + 1}/{voucher_qr.read().len()}" }
+                                    }
+                                }
+                            }
+                            textarea { readonly: true, value: "{voucher_text}", style: "min-height: 60px; padding: 8px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #94a3b8; font-size: 11px;" }
+                        }
+                        textarea { value: "{voucher_input}", oninput: move |e| voucher_input.set(e.value().clone()), placeholder: "Paste voucher text (one part per line)", style: "min-height: 60px; padding: 8px; border-radius: 10px; background: #0f172a; border: 1px solid #334155; color: #e0e0e0; font-size: 11px;" }
+                        div { style: "display: flex; gap: 8px;",
+                            button { onclick: import_voucher, style: "flex: 1; padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Import Voucher" }
+                            input { id: "voucher-image", r#type: "file", accept: "image/*", style: "flex: 1; min-width: 0; color: #94a3b8; font-size: 11px;" }
+                            button { onclick: scan_voucher_image, style: "flex: 1; padding: 10px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155;", "Scan Image" }
+                        }
+                        if let Some(v) = imported_voucher.read().as_ref() {
+                            div { style: "display: flex; gap: 8px; align-items: center; font-size: 12px; color: #a7f3d0;",
+                                span { style: "flex: 1;", "Using voucher for {format_amount(v.amount, 6)} USDC (nonce {v.nonce})" }
+                                button { onclick: move |_| imported_voucher.set(None), style: "padding: 6px 10px; border-radius: 8px; background: #0f172a; color: #94a3b8; border: 1px solid #334155;", "Clear" }
+                            }
+                        }
+                    }
                 }
             }
 
             if let Some(msg) = status.read().as_ref() {
                 div { style: "margin-top: 12px; color: #fca5a5; font-size: 12px;", "{msg}" }
             }
+            if let Some(sig) = last_tx.read().as_ref() {
+                a { href: "{config::explorer_tx_url(sig)}", target: "_blank", style: "display: block; margin-top: 6px; color: #60a5fa; font-size: 12px;", "View last transaction" }
+            }
+            if !pending_txs.read().is_empty() {
+                div { style: "margin-top: 12px; padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #f59e0b; display: grid; gap: 4px; font-size: 12px; color: #fde68a;",
+                    "Still waiting on these transactions from before the page reloaded:"
+                    for tx in pending_txs.read().iter() {
+                        a {
+                            key: "{tx.signature}",
+                            href: "{config::explorer_tx_url(&tx.signature)}",
+                            target: "_blank",
+                            style: "color: #60a5fa; word-break: break-all;",
+                            "{tx.signature.chars().take(16).collect::<String>()}..."
+                        }
+                    }
+                }
+            }
+            if let Some(url) = return_url.read().as_ref() {
+                a { href: "{url}", style: "display: block; margin-top: 12px; padding: 12px; border-radius: 10px; background: #10b981; color: #fff; text-align: center; font-weight: 600; text-decoration: none;", "Return to merchant" }
+            }
+            button {
+                onclick: clear_saved_data,
+                style: "display: block; margin: 16px auto 0; background: transparent; color: #64748b; border: none; cursor: pointer; font-size: 11px; text-decoration: underline;",
+                "Clear saved data"
+            }
         }
     }
 }
 
-fn parse_amount(value: &str, decimals: u8) -> Result<u64, String> {
-    let mut parts = value.split('.');
-    let whole = parts.next().unwrap_or("0");
-    let frac = parts.next();
-    if parts.next().is_some() {
-        return Err("invalid amount format".to_string());
+pub(crate) fn format_ts(ts: i64) -> String {
+    let date = Date::new(&wasm_bindgen::JsValue::from_f64((ts as f64) * 1000.0));
+    date.to_string().into()
+}
+
+fn short_address(address: &str) -> String {
+    if address.len() <= 8 {
+        return address.to_string();
     }
+    format!("{}…{}", &address[..4], &address[address.len() - 4..])
+}
 
-    let whole_val: u64 = whole.parse().map_err(|_| "invalid number")?;
+pub(crate) fn format_amount(amount: u64, decimals: u8) -> String {
     let scale = 10u64.pow(decimals as u32);
-    let mut amount = whole_val
-        .checked_mul(scale)
-        .ok_or("amount overflow")?;
+    let whole = amount / scale;
+    let frac = amount % scale;
+    if frac == 0 {
+        return whole.to_string();
+    }
+    let frac = format!("{:0width$}", frac, width = decimals as usize);
+    format!("{}.{}", whole, frac.trim_end_matches('0'))
+}
 
-    if let Some(frac_str) = frac {
-        if frac_str.len() > decimals as usize {
-            return Err("too many decimal places".to_string());
+fn parse_spending_rules(
+    max_per_withdraw: &str,
+    cooldown_minutes: &str,
+    daily_cap: &str,
+) -> Result<SpendingRules, String> {
+    let mut rules = SpendingRules::default();
+    if !max_per_withdraw.trim().is_empty() {
+        rules.max_per_withdraw = parse_amount(max_per_withdraw.trim(), 6)?;
+    }
+    if !cooldown_minutes.trim().is_empty() {
+        let minutes: i64 = cooldown_minutes
+            .trim()
+            .parse()
+            .map_err(|_| "invalid cooldown minutes")?;
+        if minutes < 0 {
+            return Err("invalid cooldown minutes".to_string());
         }
-        let frac_val: u64 = if frac_str.is_empty() { 0 } else { frac_str.parse().map_err(|_| "invalid fraction")? };
-        let frac_scale = 10u64.pow((decimals as usize - frac_str.len()) as u32);
-        amount = amount
-            .checked_add(frac_val * frac_scale)
-            .ok_or("amount overflow")?;
+        rules.min_interval_secs = minutes.checked_mul(60).ok_or("invalid cooldown minutes")?;
     }
+    if !daily_cap.trim().is_empty() {
+        rules.window_cap = parse_amount(daily_cap.trim(), 6)?;
+        rules.window_secs = 24 * 3600;
+    }
+    Ok(rules)
+}
 
-    Ok(amount)
+fn describe_rules(rules: &SpendingRules) -> String {
+    let mut parts = Vec::new();
+    if rules.max_per_withdraw > 0 {
+        parts.push(format!("max {} per pull", format_amount(rules.max_per_withdraw, 6)));
+    }
+    if rules.min_interval_secs > 0 {
+        parts.push(format!("{} min cooldown", rules.min_interval_secs / 60));
+    }
+    if rules.window_secs > 0 && rules.window_cap > 0 {
+        parts.push(format!(
+            "{} per {}h",
+            format_amount(rules.window_cap, 6),
+            rules.window_secs / 3600
+        ));
+    }
+    if parts.is_empty() {
+        return String::new();
+    }
+    format!(", limits: {}", parts.join(", "))
 }
 
-fn format_ts(ts: i64) -> String {
-    let date = Date::new(&wasm_bindgen::JsValue::from_f64((ts as f64) * 1000.0));
-    date.to_string().into()
+/// Runs the program's spending rules for a pull of `amount`; shared by
+/// locally signed and imported vouchers.
+fn check_withdraw_allowed(state: &DelegationState, amount: u64, now: i64) -> Result<(), String> {
+    let violations = check_spending_rules(state, amount, now);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let reasons: Vec<String> = violations.iter().map(describe_violation).collect();
+    Err(format!("Withdraw blocked: {}", reasons.join("; ")))
 }
 
-async fn fetch_nonce(
+fn describe_violation(violation: &SpendingViolation) -> String {
+    match violation {
+        SpendingViolation::MaxPerWithdraw { max } => {
+            format!("exceeds max per pull of {} USDC", format_amount(*max, 6))
+        }
+        SpendingViolation::Cooldown { next_allowed_ts } => {
+            format!("cooldown active until {}", format_ts(*next_allowed_ts))
+        }
+        SpendingViolation::WindowCap { cap, remaining, resets_ts } => format!(
+            "daily cap of {} USDC leaves {} USDC until {}",
+            format_amount(*cap, 6),
+            format_amount(*remaining, 6),
+            format_ts(*resets_ts)
+        ),
+    }
+}
+
+async fn fetch_state(
     program_id: &Pubkey,
     mint: &Pubkey,
     beneficiary: &Pubkey,
     delegator: &Pubkey,
-) -> Result<u64, String> {
+) -> Result<DelegationState, String> {
     let addrs = derive_addresses(program_id, delegator, beneficiary, mint);
     let data_b64 =
-        get_account_data_base64(RPC_URL, &addrs.delegation_pda.to_string()).await?;
+        get_account_data_base64(&rpc_url(), &addrs.delegation_pda.to_string()).await?;
     let data = decode_base64(&data_b64)?;
-    parse_nonce_from_state(&data)
+    parse_delegation_state(&data)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn sign_voucher(
+    adapter: &WalletAdapter,
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    delegator: &Pubkey,
+    beneficiary: &Pubkey,
+    amount: u64,
+    expiry_minutes: i64,
+    passkey_pubkey_b64: &str,
+    passkey_cred_id_b64: &str,
+) -> Result<WithdrawVoucher, String> {
+    let state = fetch_state(program_id, mint, beneficiary, delegator)
+        .await
+        .map_err(|e| format!("Delegation not found or invalid: {}", e))?;
+    let now = (Date::now() / 1000.0) as i64;
+    check_withdraw_allowed(&state, amount, now)?;
+    let auth_expiry_ts = now + expiry_minutes * 60;
+
+    let addrs = derive_addresses(program_id, delegator, beneficiary, mint);
+    let message = build_withdraw_message(
+        program_id,
+        &addrs.delegation_pda,
+        &addrs.source_ata,
+        &addrs.destination_ata,
+        amount,
+        state.nonce,
+        auth_expiry_ts,
+    );
+    let mut hasher = Sha256::new();
+    hasher.update(&message);
+    let challenge = hasher.finalize();
+    let challenge_b64 = B64.encode(challenge);
+
+    let passkey_sig = adapter
+        .sign_passkey(&challenge_b64, passkey_cred_id_b64)
+        .await?;
+    Ok(WithdrawVoucher {
+        delegator: delegator.to_string(),
+        beneficiary: beneficiary.to_string(),
+        mint: mint.to_string(),
+        amount,
+        nonce: state.nonce,
+        auth_expiry_ts,
+        auth_pubkey: decode_base64(passkey_pubkey_b64)?,
+        authenticator_data: decode_base64(&passkey_sig.authenticator_data_b64)?,
+        client_data_json: decode_base64(&passkey_sig.client_data_json_b64)?,
+        signature: decode_base64(&passkey_sig.signature_b64)?,
+    })
 }
@@ -0,0 +1,74 @@
+use dioxus::prelude::*;
+
+use super::delegation_modal::{format_amount, format_ts};
+use crate::config::{explorer_address_url, explorer_tx_url};
+use crate::ledger::Ledger;
+use crate::payment_request::percent_encode;
+
+#[component]
+pub fn LedgerPanel(ledger: Ledger) -> Element {
+    let decimals = ledger.decimals;
+    let short: String = ledger.delegation.chars().take(8).collect();
+    let csv_href = format!(
+        "data:text/csv;charset=utf-8,{}",
+        percent_encode(&ledger.to_csv())
+    );
+    let json_href = format!(
+        "data:application/json;charset=utf-8,{}",
+        percent_encode(&ledger.to_json().unwrap_or_default())
+    );
+    let pulls = ledger.entries.iter().filter(|e| !e.failed).count();
+
+    rsx! {
+        div { style: "padding: 10px; border-radius: 12px; background: #0b1220; border: 1px solid #334155; display: grid; gap: 8px; font-size: 12px; color: #cbd5f5;",
+            a { href: "{explorer_address_url(&ledger.delegation)}", target: "_blank", style: "color: #60a5fa; font-size: 11px;",
+                "Delegation {short}... on explorer"
+            }
+            div { style: "display: flex; justify-content: space-between;",
+                span { "Pulls: {pulls}" }
+                span { "Total: {format_amount(ledger.total_withdrawn, decimals)} USDC" }
+            }
+            if ledger.total_received != ledger.total_withdrawn {
+                div { style: "color: #94a3b8;", "Received at destination: {format_amount(ledger.total_received, decimals)} USDC" }
+            }
+            if ledger.total_fees > 0 {
+                div { style: "color: #94a3b8;", "Protocol fees: {format_amount(ledger.total_fees, decimals)} USDC" }
+            }
+            if let Some(remaining) = ledger.remaining_allowance {
+                div { style: "color: #94a3b8;", "Remaining allowance: {format_amount(remaining, decimals)} USDC" }
+            }
+            if ledger.entries.is_empty() {
+                div { style: "color: #64748b;", "No withdraws yet" }
+            }
+            for entry in ledger.entries.iter() {
+                div { key: "{entry.signature}", style: "display: grid; grid-template-columns: 1fr auto; gap: 2px; padding: 6px 0; border-top: 1px solid #1e293b;",
+                    span { style: "color: #e0e0e0;",
+                        {entry.block_time.map(format_ts).unwrap_or_else(|| format!("slot {}", entry.slot))}
+                    }
+                    span { style: if entry.failed { "color: #f87171; font-weight: 600;" } else { "color: #a7f3d0; font-weight: 600;" },
+                        "{format_amount(entry.received.unwrap_or(entry.amount), decimals)} USDC"
+                    }
+                    if let Some(fee) = entry.protocol_fee.filter(|f| *f > 0) {
+                        span { style: "grid-column: 1 / -1; color: #94a3b8; font-size: 11px;",
+                            "Gross {format_amount(entry.amount, decimals)} · fee {format_amount(fee, decimals)} · net {format_amount(entry.received.unwrap_or(entry.amount.saturating_sub(fee)), decimals)}"
+                        }
+                    }
+                    a { href: "{explorer_tx_url(&entry.signature)}", target: "_blank", style: "color: #64748b; font-size: 11px;",
+                        "{entry.signature.chars().take(16).collect::<String>()}..."
+                    }
+                    span { style: "color: #64748b; font-size: 11px;",
+                        if entry.failed {
+                            "failed"
+                        } else if let Some(nonce) = entry.nonce {
+                            "nonce {nonce}"
+                        }
+                    }
+                }
+            }
+            div { style: "display: flex; gap: 8px;",
+                a { href: "{csv_href}", download: "ledger-{short}.csv", style: "flex: 1; padding: 8px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155; text-align: center; text-decoration: none;", "Export CSV" }
+                a { href: "{json_href}", download: "ledger-{short}.json", style: "flex: 1; padding: 8px; border-radius: 10px; background: #0f172a; color: #cbd5f5; border: 1px solid #334155; text-align: center; text-decoration: none;", "Export JSON" }
+            }
+        }
+    }
+}
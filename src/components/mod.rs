@@ -1,5 +1,17 @@
 pub mod swap_modal;
 pub mod delegation_modal;
+pub mod ledger_panel;
+pub mod token_picker;
+pub mod route_panel;
+pub mod cluster_selector;
+pub mod admin_panel;
+pub mod wallet_picker;
 
 pub use swap_modal::SwapModal;
 pub use delegation_modal::DelegationModal;
+pub use ledger_panel::LedgerPanel;
+pub use token_picker::TokenPicker;
+pub use route_panel::RoutePanel;
+pub use cluster_selector::ClusterSelector;
+pub use admin_panel::AdminPanel;
+pub use wallet_picker::WalletPicker;
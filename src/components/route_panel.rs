@@ -0,0 +1,55 @@
+use dioxus::prelude::*;
+
+use super::delegation_modal::format_amount;
+use crate::api::Quote;
+use crate::token_registry::TokenRegistry;
+
+#[component]
+pub fn RoutePanel(quotes: Vec<Quote>, registry: Signal<TokenRegistry>) -> Element {
+    let describe = move |mint: &str, amount: u64| match registry.read().by_mint(mint) {
+        Some(t) => format!("{} {}", format_amount(amount, t.decimals), t.symbol),
+        None => format!("{} {}…", amount, &mint[..mint.len().min(4)]),
+    };
+    let symbol = move |mint: &str| match registry.read().by_mint(mint) {
+        Some(t) => t.symbol.clone(),
+        None => format!("{}…", &mint[..mint.len().min(4)]),
+    };
+
+    rsx! {
+        div { style: "background: #0b1220; border: 1px solid #334155; border-radius: 12px; padding: 12px; margin-bottom: 16px; display: grid; gap: 10px; font-size: 12px; color: #cbd5f5;",
+            div { style: "color: #64748b; text-transform: uppercase; letter-spacing: 1px;", "Routes" }
+            for quote in quotes {
+                div { key: "{quote.provider.label()}", style: "display: grid; gap: 4px;",
+                    div { style: "display: flex; justify-content: space-between;",
+                        span { style: "font-weight: 600; color: #e0e0e0;", "{quote.provider.label()}" }
+                        span { style: "color: #64748b;",
+                            "{quote.route.len()} hop(s)"
+                        }
+                    }
+                    if quote.route.is_empty() {
+                        div { style: "color: #64748b;", "Route not disclosed by provider" }
+                    }
+                    for (i, leg) in quote.route.iter().enumerate() {
+                        div {
+                            key: "{i}",
+                            style: "display: flex; justify-content: space-between; gap: 8px; padding-left: 8px; border-left: 2px solid #334155;",
+                            title: "{leg.amm_key.clone().unwrap_or_default()}",
+                            span {
+                                span { style: "color: #93c5fd;", "{leg.label}" }
+                                " {symbol(&leg.input_mint)} → {symbol(&leg.output_mint)}"
+                                if leg.percent < 100 {
+                                    span { style: "color: #94a3b8;", " ({leg.percent}%)" }
+                                }
+                            }
+                            if leg.in_amount > 0 && leg.out_amount > 0 {
+                                span { style: "color: #94a3b8;",
+                                    "{describe(&leg.input_mint, leg.in_amount)} → {describe(&leg.output_mint, leg.out_amount)}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
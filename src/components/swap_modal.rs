@@ -1,163 +1,309 @@
+use dioxus::core::Task;
 use dioxus::prelude::*;
-use crate::api::{JupiterClient, DflowClient, TitanClient};
-use crate::wallet::WalletAdapter;
-
-const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
-const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
-
-#[derive(Clone, PartialEq)]
-pub struct Token {
-    pub mint: String,
-    pub symbol: String,
-    pub name: String,
-    pub decimals: u8,
-}
-
-impl Token {
-    pub fn sol() -> Self {
-        Self {
-            mint: SOL_MINT.to_string(),
-            symbol: "SOL".to_string(),
-            name: "Solana".to_string(),
-            decimals: 9,
-        }
-    }
+use crate::api::execute::{execute_quote, ExecutionResult};
+use crate::api::quote::{fetch_with_timeout, ProviderQuote, QuoteStatus, SwapMode};
+use crate::api::ranking::{best_executable, rank_quotes};
+use crate::api::slippage::{format_bps, Slippage, HIGH_SLIPPAGE_BPS, SLIPPAGE_PRESETS_BPS};
+use crate::api::{default_providers, Quote, QuoteRequest};
+use crate::balances::{fetch_balances, WalletBalances};
+use crate::components::delegation_modal::format_amount;
+use crate::components::{RoutePanel, TokenPicker, WalletPicker};
+use crate::config::{self, rpc_url};
+use crate::timed_delegation::parse_amount;
+use crate::token_registry::{Token, TokenRegistry, SOL_MINT};
+use crate::wallet_context::use_wallet;
 
-    pub fn usdc() -> Self {
-        Self {
-            mint: USDC_MINT.to_string(),
-            symbol: "USDC".to_string(),
-            name: "USD Coin".to_string(),
-            decimals: 6,
-        }
-    }
+#[derive(Clone, Copy, PartialEq)]
+enum PickerSide {
+    Input,
+    Output,
 }
 
 #[component]
 pub fn SwapModal() -> Element {
-    let mut wallet = use_signal(|| WalletAdapter::new());
-    let mut wallet_address = use_signal(|| Option::<String>::None);
-    let mut input_token = use_signal(|| Token::sol());
-    let mut output_token = use_signal(|| Token::usdc());
+    let wallet_ctx = use_wallet();
+    let wallet = wallet_ctx.adapter;
+    let wallet_address = use_memo(move || wallet.read().get_public_key());
+    let mut input_token = use_signal(Token::sol);
+    let mut output_token = use_signal(Token::usdc);
     let mut input_amount = use_signal(|| String::from(""));
-    let mut quote_loading = use_signal(|| false);
-    let mut jupiter_quote = use_signal(|| Option::<String>::None);
-    let mut dflow_quote = use_signal(|| Option::<String>::None);
-    let mut titan_quote = use_signal(|| Option::<String>::None);
+    let mut registry = use_signal(TokenRegistry::load);
+    let mut picker_side = use_signal(|| Option::<PickerSide>::None);
+    let mut provider_quotes = use_signal(Vec::<ProviderQuote>::new);
+    let mut quote_tasks = use_signal(Vec::<Task>::new);
     let mut error_message = use_signal(|| Option::<String>::None);
+    let mut executing = use_signal(|| false);
+    let mut execution = use_signal(|| Option::<ExecutionResult>::None);
+    let mut balances = use_signal(|| Option::<WalletBalances>::None);
+    let mut slippage = use_signal(Slippage::default);
+    let mut swap_mode = use_signal(SwapMode::default);
+    let mut show_routes = use_signal(|| false);
+    let mut show_wallet_picker = use_signal(|| false);
+    let mut custom_slippage = use_signal(String::new);
+    let mut slippage_error = use_signal(|| Option::<String>::None);
 
-    let connect_wallet = move |_| {
+    // Refresh holdings whenever the wallet changes or a swap lands.
+    use_effect(move || {
+        let owner = wallet_address.read().clone();
+        let _ = execution.read();
+        let Some(owner) = owner else {
+            balances.set(None);
+            return;
+        };
         spawn(async move {
-            match WalletAdapter::connect_wallet().await {
-                Ok(address) => {
-                    log::info!("Wallet connected: {}", address);
-                    wallet_address.set(Some(address));
-                    error_message.set(None);
-                }
-                Err(e) => {
-                    log::error!("Wallet connection failed: {}", e);
-                    error_message.set(Some(format!("Failed to connect wallet: {}", e)));
-                }
+            match fetch_balances(&rpc_url(), &owner).await {
+                Ok(b) => balances.set(Some(b)),
+                Err(e) => log::error!("Failed to load balances: {}", e),
+            }
+        });
+    });
+
+    let connect_wallet = move |_| show_wallet_picker.set(true);
+
+    let on_wallet_connected = move |address: String| {
+        log::info!("Wallet connected: {}", address);
+        show_wallet_picker.set(false);
+        error_message.set(None);
+    };
+
+    let disconnect_wallet = move |_| {
+        spawn(async move {
+            if let Err(e) = wallet_ctx.disconnect().await {
+                log::warn!("Wallet disconnect failed: {}", e);
             }
         });
     };
 
-    // Auto-fetch quotes when amount changes (with debouncing)
+    // Auto-fetch quotes when amount changes (with debouncing). Each provider
+    // runs in its own task; a new input cancels whatever is still in flight.
     use_effect(move || {
         let input_val = input_amount.read().clone();
-        
-        if input_val.is_empty() || wallet_address.read().is_none() {
-            jupiter_quote.set(None);
-            dflow_quote.set(None);
-            titan_quote.set(None);
+
+        for task in quote_tasks.write().drain(..) {
+            task.cancel();
+        }
+
+        if input_val.is_empty()
+            || wallet_address.read().is_none()
+            || !config::cluster().has_aggregators()
+        {
+            provider_quotes.set(Vec::new());
             return;
         }
 
         let amount: f64 = match input_val.parse() {
             Ok(v) if v > 0.0 => v,
-            _ => return,
+            _ => {
+                provider_quotes.set(Vec::new());
+                return;
+            }
         };
 
         let input_tok = input_token.read().clone();
         let output_tok = output_token.read().clone();
-        let lamports = (amount * 10_f64.powi(input_tok.decimals as i32)) as u64;
+        let slippage_setting = *slippage.read();
+        let mode = *swap_mode.read();
+        // ExactOut amounts are typed in the output token.
+        let typed_decimals = match mode {
+            SwapMode::ExactIn => input_tok.decimals,
+            SwapMode::ExactOut => output_tok.decimals,
+        };
+        let lamports = (amount * 10_f64.powi(typed_decimals as i32)) as u64;
 
-        quote_loading.set(true);
         error_message.set(None);
 
+        let request = QuoteRequest {
+            input_mint: input_tok.mint.clone(),
+            output_mint: output_tok.mint.clone(),
+            amount: lamports,
+            swap_mode: mode,
+            slippage_bps: slippage_setting.bps_for(&input_tok.mint, &output_tok.mint),
+            auto_slippage: slippage_setting.is_auto(),
+            taker: wallet_address.peek().clone(),
+        };
+        let providers = default_providers();
+        provider_quotes.set(providers.iter().map(|p| ProviderQuote::pending(p.id())).collect());
+
+        let tasks = providers
+            .into_iter()
+            .map(|provider| {
+                let request = request.clone();
+                spawn(async move {
+                    // Debounce - wait 500ms before fetching
+                    gloo_timers::future::sleep(std::time::Duration::from_millis(500)).await;
+
+                    let name = provider.id().label();
+                    log::info!("Fetching {} quote for {} {} -> {}",
+                        name, request.amount, request.input_mint, request.output_mint);
+                    let result = fetch_with_timeout(provider.as_ref(), &request).await;
+                    match &result.status {
+                        QuoteStatus::Ready(quote) => {
+                            log::info!("{} quote: {} -> {}", name, quote.in_amount, quote.out_amount)
+                        }
+                        QuoteStatus::Failed(e) => log::error!("{} quote failed: {}", name, e),
+                        QuoteStatus::TimedOut => log::error!("{} quote timed out", name),
+                        QuoteStatus::Unsupported => {
+                            log::info!("{} does not support {}", name, request.swap_mode.as_str())
+                        }
+                        QuoteStatus::Pending => {}
+                    }
+                    if let Some(slot) = provider_quotes
+                        .write()
+                        .iter_mut()
+                        .find(|q| q.provider == result.provider)
+                    {
+                        *slot = result;
+                    }
+                })
+            })
+            .collect();
+        quote_tasks.set(tasks);
+    });
+
+    let swap_tokens = move |_| {
+        let temp = input_token.read().clone();
+        input_token.set(output_token.read().clone());
+        output_token.set(temp);
+        provider_quotes.set(Vec::new());
+    };
+
+    let mut execute_best = move |quote: Quote| {
+        let Some(taker) = wallet_address.read().clone() else {
+            return;
+        };
+        executing.set(true);
+        execution.set(None);
+        error_message.set(None);
         spawn(async move {
-            // Debounce - wait 500ms before fetching
-            gloo_timers::future::sleep(std::time::Duration::from_millis(500)).await;
-            
-            // Get Jupiter quote with user pubkey
-            let user_pk = wallet_address.read().clone();
-            log::info!("Fetching Jupiter quote for {} {} -> {}", 
-                lamports, input_tok.symbol, output_tok.symbol);
-            
-            match JupiterClient::get_quote(
-                &input_tok.mint, 
-                &output_tok.mint, 
-                lamports, 
-                50,
-                user_pk.as_deref()
-            ).await {
-                Ok(quote) => {
-                    log::info!("Jupiter raw response - in: {}, out: {}", 
-                        quote.in_amount, quote.out_amount);
-                    
-                    let out_amount = quote.out_amount.parse::<u64>().unwrap_or(0);
-                    let out_val = out_amount as f64 / 10_f64.powi(output_tok.decimals as i32);
-                    jupiter_quote.set(Some(format!("{:.6} {}", out_val, output_tok.symbol)));
-                    log::info!("Jupiter quote SUCCESS: {} {}", out_val, output_tok.symbol);
+            let adapter = wallet.read().clone();
+            match execute_quote(&adapter, &quote, &taker, &rpc_url()).await {
+                Ok(result) => {
+                    log::info!("Swap landed via {}: {}", result.provider.label(), result.signature);
+                    execution.set(Some(result));
+                    input_amount.set(String::new());
                 }
                 Err(e) => {
-                    log::error!("Jupiter quote FAILED: {}", e);
+                    log::error!("Swap failed: {}", e);
+                    error_message.set(Some(format!("Swap failed: {}", e)));
                 }
             }
+            executing.set(false);
+        });
+    };
 
-            // Get Dflow quote via hermes proxy
-            match DflowClient::get_quote(
-                &input_tok.mint, 
-                &output_tok.mint, 
-                lamports, 
-                50
-            ).await {
-                Ok(quote) => {
-                    let out_amount = quote.out_amount.parse::<u64>().unwrap_or(0);
-                    let out_val = out_amount as f64 / 10_f64.powi(output_tok.decimals as i32);
-                    dflow_quote.set(Some(format!("{:.6} {}", out_val, output_tok.symbol)));
-                    log::info!("Dflow quote: {} {}", out_val, output_tok.symbol);
-                }
-                Err(e) => {
-                    log::error!("Dflow quote failed: {}", e);
-                }
-            }
-            
-            // Get Titan quote via hermes WebSocket proxy
-            let mut titan = TitanClient::default();
-            match titan.get_quote(&input_tok.mint, &output_tok.mint, lamports, 50).await {
-                Ok(quote) => {
-                    let out_amount = quote.out_amount.parse::<u64>().unwrap_or(0);
-                    let out_val = out_amount as f64 / 10_f64.powi(output_tok.decimals as i32);
-                    titan_quote.set(Some(format!("{:.6} {}", out_val, output_tok.symbol)));
-                    log::info!("Titan quote: {} {}", out_val, output_tok.symbol);
-                }
-                Err(e) => {
-                    log::error!("Titan quote failed: {}", e);
-                }
+    let select_token = move |token: Token| {
+        let Some(side) = *picker_side.read() else {
+            return;
+        };
+        let (mut target, mut other) = match side {
+            PickerSide::Input => (input_token, output_token),
+            PickerSide::Output => (output_token, input_token),
+        };
+        if other.read().mint == token.mint {
+            other.set(target.read().clone());
+        }
+        target.set(token.clone());
+        registry.write().mark_used(&token);
+        provider_quotes.set(Vec::new());
+        picker_side.set(None);
+    };
+
+    let mut fill_fraction = move |numerator: u64, denominator: u64| {
+        let token = input_token.read().clone();
+        let Some(spendable) = balances.read().as_ref().map(|b| b.spendable(&token.mint)) else {
+            return;
+        };
+        let amount = (spendable as u128 * numerator as u128 / denominator as u128) as u64;
+        input_amount.set(format_amount(amount, token.decimals));
+    };
+
+    let input_balance = balances
+        .read()
+        .as_ref()
+        .map(|b| format_amount(b.balance_of(&input_token.read().mint), input_token.read().decimals));
+    let output_balance = balances
+        .read()
+        .as_ref()
+        .map(|b| format_amount(b.balance_of(&output_token.read().mint), output_token.read().decimals));
+    let mut set_custom_slippage = move |value: String| {
+        custom_slippage.set(value.clone());
+        if value.trim().is_empty() {
+            slippage_error.set(None);
+            return;
+        }
+        match Slippage::from_percent(&value) {
+            Ok(s) => {
+                slippage.set(s);
+                slippage_error.set(None);
             }
+            Err(e) => slippage_error.set(Some(e)),
+        }
+    };
+    let effective_slippage_bps = slippage
+        .read()
+        .bps_for(&input_token.read().mint, &output_token.read().mint);
+    let high_slippage = !slippage.read().is_auto() && effective_slippage_bps >= HIGH_SLIPPAGE_BPS;
 
-            quote_loading.set(false);
-        });
+    let output_decimals = output_token.read().decimals;
+    let output_symbol = output_token.read().symbol.clone();
+    let format_out = move |amount: u64| {
+        format!("{:.6} {}", amount as f64 / 10_f64.powi(output_decimals as i32), output_symbol)
+    };
+    let input_decimals = input_token.read().decimals;
+    let input_symbol = input_token.read().symbol.clone();
+    let format_in = move |amount: u64| {
+        format!("{:.6} {}", amount as f64 / 10_f64.powi(input_decimals as i32), input_symbol)
+    };
+    let mut set_mode = move |mode: SwapMode| {
+        if *swap_mode.read() != mode {
+            swap_mode.set(mode);
+            input_amount.set(String::new());
+            provider_quotes.set(Vec::new());
+        }
+    };
+    let quote_loading = provider_quotes
+        .read()
+        .iter()
+        .any(|q| q.status == QuoteStatus::Pending);
+    let ready: Vec<Quote> = provider_quotes
+        .read()
+        .iter()
+        .filter_map(|q| q.quote().cloned())
+        .collect();
+    let ranked = rank_quotes(&ready, js_sys::Date::now());
+    // The swap runs the best quote that carries a transaction; a better
+    // quote-only rate is still listed below but cannot be executed.
+    let best = best_executable(&ranked).cloned();
+    let top_rate = ranked
+        .first()
+        .filter(|r| !r.quote.is_executable())
+        .map(|r| r.quote.provider);
+    let exact_out = *swap_mode.read() == SwapMode::ExactOut;
+    let best_quote = best.as_ref().map(|b| {
+        if exact_out {
+            format_in(b.quote.in_amount)
+        } else {
+            format_out(b.net_out)
+        }
     });
-
-    let swap_tokens = move |_| {
-        let temp = input_token.read().clone();
-        input_token.set(output_token.read().clone());
-        output_token.set(temp);
-        jupiter_quote.set(None);
-        dflow_quote.set(None);
-        titan_quote.set(None);
+    let balance_error = {
+        let token = input_token.read();
+        let value = input_amount.read();
+        // ExactOut spends at most the best quote's slippage bound.
+        let spend = match *swap_mode.read() {
+            SwapMode::ExactIn => parse_amount(value.trim(), token.decimals).ok(),
+            SwapMode::ExactOut => best.as_ref().map(|b| b.quote.max_in_amount),
+        };
+        match (balances.read().as_ref(), spend) {
+            (Some(b), Some(amount)) if !value.trim().is_empty() && amount > b.spendable(&token.mint) => {
+                Some(if token.mint == SOL_MINT {
+                    format!("Insufficient {} (keeping a small reserve for fees)", token.symbol)
+                } else {
+                    format!("Insufficient {} balance", token.symbol)
+                })
+            }
+            _ => None,
+        }
     };
 
     rsx! {
@@ -170,7 +316,12 @@ pub fn SwapModal() -> Element {
             }
 
             // Wallet connection
-            if wallet_address.read().is_none() {
+            if *show_wallet_picker.read() {
+                WalletPicker {
+                    on_connected: on_wallet_connected,
+                    on_close: move |_| show_wallet_picker.set(false),
+                }
+            } else if wallet_address.read().is_none() {
                 div {
                     id: "connect-wallet-button",
                     style: "margin-bottom: 24px;",
@@ -195,7 +346,35 @@ pub fn SwapModal() -> Element {
             } else {
                 div {
                     style: "color: #94a3b8; margin-bottom: 24px; font-size: 14px; text-align: center;",
-                    "Connected: {wallet_address.read().as_ref().unwrap().chars().take(8).collect::<String>()}..."
+                    "Connected: {wallet_address.read().as_ref().unwrap().chars().take(8).collect::<String>()}... "
+                    button {
+                        onclick: disconnect_wallet,
+                        style: "background: transparent; color: #60a5fa; border: none; cursor: pointer; font-size: 12px;",
+                        "Disconnect"
+                    }
+                }
+            }
+
+            if picker_side.read().is_some() {
+                TokenPicker {
+                    registry,
+                    on_select: select_token,
+                    on_close: move |_| picker_side.set(None),
+                }
+            }
+
+            // Swap mode
+            div {
+                style: "display: flex; justify-content: flex-end; gap: 6px; margin-bottom: 8px;",
+                button {
+                    onclick: move |_| set_mode(SwapMode::ExactIn),
+                    style: if *swap_mode.read() == SwapMode::ExactIn { "background: #3b82f6; color: white; border: 1px solid #3b82f6; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" } else { "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" },
+                    "Exact in"
+                }
+                button {
+                    onclick: move |_| set_mode(SwapMode::ExactOut),
+                    style: if *swap_mode.read() == SwapMode::ExactOut { "background: #3b82f6; color: white; border: 1px solid #3b82f6; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" } else { "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" },
+                    "Exact out"
                 }
             }
 
@@ -206,22 +385,68 @@ pub fn SwapModal() -> Element {
                 div {
                     style: "display: flex; justify-content: space-between; margin-bottom: 8px;",
                     span { style: "color: #94a3b8; font-size: 14px;", "You pay" }
-                    span { style: "color: #94a3b8; font-size: 14px;", "{input_token.read().symbol}" }
+                    button {
+                        onclick: move |_| picker_side.set(Some(PickerSide::Input)),
+                        style: "color: #e0e0e0; font-size: 14px; background: #1e293b; border: 1px solid #334155; border-radius: 999px; padding: 2px 10px; cursor: pointer;",
+                        "{input_token.read().symbol} ▾"
+                    }
                 }
-                
-                input {
-                    r#type: "text",
-                    value: "{input_amount}",
-                    oninput: move |e| input_amount.set(e.value().clone()),
-                    placeholder: "0.0",
-                    style: "
-                        width: 100%;
-                        background: transparent;
-                        border: none;
-                        color: white;
-                        font-size: 32px;
-                        outline: none;
-                    "
+
+                if let Some(ref bal) = input_balance {
+                    div {
+                        style: "display: flex; justify-content: flex-end; align-items: center; gap: 6px; margin-bottom: 4px;",
+                        span { style: "color: #64748b; font-size: 12px;", "Balance: {bal}" }
+                        if !exact_out {
+                            button {
+                                onclick: move |_| fill_fraction(1, 2),
+                                style: "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 0 6px; font-size: 11px; cursor: pointer;",
+                                "Half"
+                            }
+                            button {
+                                onclick: move |_| fill_fraction(1, 1),
+                                style: "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 0 6px; font-size: 11px; cursor: pointer;",
+                                "Max"
+                            }
+                        }
+                    }
+                }
+
+                if exact_out {
+                    div {
+                        style: "color: white; font-size: 32px;",
+                        if let Some(ref cost) = best_quote {
+                            "{cost}"
+                        } else if quote_loading {
+                            span { class: "pulse-loader", style: "color: #64748b; font-size: 16px;", "Fetching best rates..." }
+                        } else {
+                            "0.0"
+                        }
+                    }
+                    if let Some(ref b) = best {
+                        div {
+                            style: "color: #94a3b8; font-size: 12px; margin-top: 2px;",
+                            "Maximum spent: {format_in(b.quote.max_in_amount)}"
+                        }
+                    }
+                } else {
+                    input {
+                        r#type: "text",
+                        value: "{input_amount}",
+                        oninput: move |e| input_amount.set(e.value().clone()),
+                        placeholder: "0.0",
+                        style: "
+                            width: 100%;
+                            background: transparent;
+                            border: none;
+                            color: white;
+                            font-size: 32px;
+                            outline: none;
+                        "
+                    }
+                }
+
+                if let Some(ref err) = balance_error {
+                    div { style: "color: #ef4444; font-size: 12px; margin-top: 4px;", "{err}" }
                 }
             }
 
@@ -249,10 +474,48 @@ pub fn SwapModal() -> Element {
                 div {
                     style: "display: flex; justify-content: space-between; margin-bottom: 8px;",
                     span { style: "color: #94a3b8; font-size: 14px;", "You receive" }
-                    span { style: "color: #94a3b8; font-size: 14px;", "{output_token.read().symbol}" }
+                    button {
+                        onclick: move |_| picker_side.set(Some(PickerSide::Output)),
+                        style: "color: #e0e0e0; font-size: 14px; background: #1e293b; border: 1px solid #334155; border-radius: 999px; padding: 2px 10px; cursor: pointer;",
+                        "{output_token.read().symbol} ▾"
+                    }
+                }
+
+                if let Some(ref bal) = output_balance {
+                    div {
+                        style: "color: #64748b; font-size: 12px; text-align: right; margin-bottom: 4px;",
+                        "Balance: {bal}"
+                    }
                 }
                 
-                if *quote_loading.read() {
+                if exact_out {
+                    input {
+                        r#type: "text",
+                        value: "{input_amount}",
+                        oninput: move |e| input_amount.set(e.value().clone()),
+                        placeholder: "0.0",
+                        style: "
+                            width: 100%;
+                            background: transparent;
+                            border: none;
+                            color: white;
+                            font-size: 32px;
+                            outline: none;
+                        "
+                    }
+                    if let Some(ref b) = best {
+                        div {
+                            style: "color: #34d399; font-size: 12px; margin-top: 4px;",
+                            "Swaps via {b.quote.provider.label()}"
+                        }
+                        if let Some(top) = top_rate {
+                            div {
+                                style: "color: #94a3b8; font-size: 12px; margin-top: 2px;",
+                                "{top.label()} quotes better but only returns a quote"
+                            }
+                        }
+                    }
+                } else if quote_loading && best_quote.is_none() {
                     // Skeleton loader with pulsing circles
                     div {
                         style: "display: flex; align-items: center; gap: 8px; padding: 8px 0;",
@@ -289,45 +552,108 @@ pub fn SwapModal() -> Element {
                     div {
                         style: "color: white; font-size: 32px;",
                         {
-                            if let Some(ref best_quote) = *jupiter_quote.read() {
+                            if let Some(ref best_quote) = best_quote {
                                 rsx! { "{best_quote}" }
                             } else {
                                 rsx! { "0.0" }
                             }
                         }
                     }
+                    if let Some(ref b) = best {
+                        div {
+                            style: "color: #34d399; font-size: 12px; margin-top: 4px;",
+                            "Swaps via {b.quote.provider.label()}"
+                        }
+                        if let Some(top) = top_rate {
+                            div {
+                                style: "color: #94a3b8; font-size: 12px; margin-top: 2px;",
+                                "{top.label()} quotes better but only returns a quote"
+                            }
+                        }
+                        div {
+                            style: "color: #94a3b8; font-size: 12px; margin-top: 2px;",
+                            "Minimum received: {format_out(b.quote.min_out_amount)}"
+                        }
+                    }
+                }
+            }
+
+            // Slippage settings
+            div {
+                style: "background: #0f172a; padding: 12px; border-radius: 12px; margin-bottom: 16px; border: 1px solid #334155;",
+                div {
+                    style: "display: flex; align-items: center; gap: 6px; flex-wrap: wrap;",
+                    span { style: "color: #94a3b8; font-size: 13px; margin-right: 4px;", "Slippage" }
+                    button {
+                        onclick: move |_| {
+                            slippage.set(Slippage::Auto);
+                            custom_slippage.set(String::new());
+                            slippage_error.set(None);
+                        },
+                        style: if slippage.read().is_auto() { "background: #3b82f6; color: white; border: 1px solid #3b82f6; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" } else { "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" },
+                        "Auto"
+                    }
+                    for bps in SLIPPAGE_PRESETS_BPS {
+                        button {
+                            key: "{bps}",
+                            onclick: move |_| {
+                                slippage.set(Slippage::Fixed(bps));
+                                custom_slippage.set(String::new());
+                                slippage_error.set(None);
+                            },
+                            style: if *slippage.read() == Slippage::Fixed(bps) && custom_slippage.read().is_empty() { "background: #3b82f6; color: white; border: 1px solid #3b82f6; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" } else { "background: #1e293b; color: #94a3b8; border: 1px solid #334155; border-radius: 6px; padding: 2px 8px; font-size: 12px; cursor: pointer;" },
+                            "{format_bps(bps)}"
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        value: "{custom_slippage}",
+                        oninput: move |e| set_custom_slippage(e.value()),
+                        placeholder: "Custom %",
+                        style: "width: 72px; background: #1e293b; color: white; border: 1px solid #334155; border-radius: 6px; padding: 2px 6px; font-size: 12px;",
+                    }
+                }
+                if slippage.read().is_auto() {
+                    div { style: "color: #64748b; font-size: 11px; margin-top: 6px;",
+                        "Auto: providers pick their own tolerance, others use {format_bps(effective_slippage_bps)} for this pair"
+                    }
+                }
+                if let Some(ref err) = *slippage_error.read() {
+                    div { style: "color: #ef4444; font-size: 12px; margin-top: 6px;", "{err}" }
+                } else if high_slippage {
+                    div { style: "color: #f59e0b; font-size: 12px; margin-top: 6px;",
+                        "High slippage ({format_bps(effective_slippage_bps)}): the swap may fill well below the quote"
+                    }
                 }
             }
 
             // Quote comparison
-            if jupiter_quote.read().is_some() || dflow_quote.read().is_some() || titan_quote.read().is_some() {
+            if !provider_quotes.read().is_empty() {
                 div {
                     id: "quote-comparison-container",
                     style: "background: linear-gradient(135deg, #1e293b 0%, #0f172a 100%); padding: 12px; border-radius: 12px; margin-bottom: 16px; border: 2px solid #3b82f6;",
                     
                     div {
-                        style: "color: #64748b; font-size: 12px; text-transform: uppercase; letter-spacing: 1px; margin-bottom: 8px;",
-                        "Quote Comparison"
-                    }
-                    
-                    if let Some(ref jup) = *jupiter_quote.read() {
-                        div {
-                            style: "
-                                color: #e0e0e0;
-                                font-size: 14px;
-                                margin-bottom: 4px;
-                                display: flex;
-                                justify-content: space-between;
-                                align-items: center;
-                                padding: 4px 0;
-                            ",
-                            span { "Jupiter" }
-                            span { style: "font-weight: 600;", "{jup}" }
+                        style: "display: flex; justify-content: space-between; align-items: center; margin-bottom: 8px;",
+                        span {
+                            style: "color: #64748b; font-size: 12px; text-transform: uppercase; letter-spacing: 1px;",
+                            "Quote Comparison"
+                        }
+                        if !ranked.is_empty() {
+                            button {
+                                onclick: move |_| {
+                                    let shown = *show_routes.read();
+                                    show_routes.set(!shown);
+                                },
+                                style: "background: transparent; color: #60a5fa; border: none; font-size: 12px; cursor: pointer;",
+                                if *show_routes.read() { "Hide routes" } else { "Show routes" }
+                            }
                         }
                     }
                     
-                    if let Some(ref df) = *dflow_quote.read() {
+                    for pq in provider_quotes.read().iter() {
                         div {
+                            key: "{pq.provider.label()}",
                             style: "
                                 color: #e0e0e0;
                                 font-size: 14px;
@@ -337,25 +663,126 @@ pub fn SwapModal() -> Element {
                                 align-items: center;
                                 padding: 4px 0;
                             ",
-                            span { "Dflow" }
-                            span { style: "font-weight: 600;", "{df}" }
+                            span {
+                                "{pq.provider.label()}"
+                                if let Some(ms) = pq.latency_ms {
+                                    span { style: "color: #64748b; font-size: 11px; margin-left: 6px;", "{ms:.0} ms" }
+                                }
+                            }
+                            match &pq.status {
+                                QuoteStatus::Pending => rsx! {
+                                    span { class: "pulse-loader", style: "color: #64748b;", "..." }
+                                },
+                                QuoteStatus::Ready(_) => {
+                                    let r = ranked.iter().find(|r| r.quote.provider == pq.provider);
+                                    let is_best = best.as_ref().map(|b| b.quote.provider) == Some(pq.provider);
+                                    rsx! {
+                                        span { style: "display: flex; align-items: center; gap: 6px;",
+                                            if let Some(r) = r {
+                                                if r.stale {
+                                                    span { style: "color: #f59e0b; font-size: 11px;", "stale" }
+                                                } else if !r.quote.is_executable() {
+                                                    span { style: "color: #64748b; font-size: 11px;", title: "This provider returns quotes without a transaction", "quote only" }
+                                                } else if is_best {
+                                                    span { style: "color: #0f172a; background: #34d399; font-size: 11px; padding: 1px 6px; border-radius: 6px;", "Best" }
+                                                } else {
+                                                    span { style: "color: #94a3b8; font-size: 11px;", if exact_out { "+{r.spread_bps} bps" } else { "-{r.spread_bps} bps" } }
+                                                }
+                                                if exact_out {
+                                                    span {
+                                                        style: "color: #64748b; font-size: 11px;",
+                                                        title: "Slippage {format_bps(r.quote.slippage_bps)}",
+                                                        "max {format_in(r.quote.max_in_amount)}"
+                                                    }
+                                                    span { style: if is_best { "font-weight: 600; color: #34d399;" } else { "font-weight: 600;" }, "{format_in(r.net_in)}" }
+                                                } else {
+                                                    span {
+                                                        style: "color: #64748b; font-size: 11px;",
+                                                        title: "Slippage {format_bps(r.quote.slippage_bps)}",
+                                                        "min {format_out(r.quote.min_out_amount)}"
+                                                    }
+                                                    span { style: if is_best { "font-weight: 600; color: #34d399;" } else { "font-weight: 600;" }, "{format_out(r.net_out)}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                QuoteStatus::Failed(e) => rsx! {
+                                    span { style: "color: #ef4444; font-size: 12px;", title: "{e}", "Error" }
+                                },
+                                QuoteStatus::TimedOut => rsx! {
+                                    span { style: "color: #f59e0b; font-size: 12px;", "Timed out" }
+                                },
+                                QuoteStatus::Unsupported => rsx! {
+                                    span { style: "color: #64748b; font-size: 12px;", "Exact in only" }
+                                },
+                            }
                         }
                     }
-                    
-                    if let Some(ref titan) = *titan_quote.read() {
+                }
+            }
+
+            if *show_routes.read() && !ranked.is_empty() {
+                RoutePanel {
+                    quotes: ranked.iter().map(|r| r.quote.clone()).collect::<Vec<_>>(),
+                    registry,
+                }
+            }
+
+            if let Some(ref b) = best {
+                button {
+                    disabled: *executing.read() || b.stale || balance_error.is_some(),
+                    onclick: {
+                        let quote = b.quote.clone();
+                        move |_| execute_best(quote.clone())
+                    },
+                    style: "
+                        width: 100%;
+                        background: linear-gradient(135deg, #3b82f6 0%, #8b5cf6 100%);
+                        color: white;
+                        padding: 16px;
+                        border-radius: 12px;
+                        border: none;
+                        font-size: 16px;
+                        font-weight: 600;
+                        cursor: pointer;
+                        margin-bottom: 16px;
+                    ",
+                    if *executing.read() {
+                        "Confirming swap..."
+                    } else if let Some(ref err) = balance_error {
+                        "{err}"
+                    } else if b.stale {
+                        "Quote expired"
+                    } else {
+                        "Swap via {b.quote.provider.label()}"
+                    }
+                }
+            }
+
+            // Execution result
+            if let Some(ref result) = *execution.read() {
+                div {
+                    style: "color: #e0e0e0; margin-bottom: 16px; font-size: 14px; padding: 12px; background: rgba(52, 211, 153, 0.1); border-radius: 8px; border: 1px solid #34d399;",
+                    div { style: "color: #34d399; font-weight: 600; margin-bottom: 6px;", "Swap confirmed via {result.provider.label()}" }
+                    div { "Quoted: {format_out(result.quoted_out)}" }
+                    if let Some(realized) = result.realized_out {
+                        div { "Received: {format_out(realized)}" }
+                    } else {
+                        div { style: "color: #94a3b8;", "Received amount unavailable" }
+                    }
+                    if let Some(bps) = result.deviation_bps() {
                         div {
-                            style: "
-                                color: #e0e0e0;
-                                font-size: 14px;
-                                display: flex;
-                                justify-content: space-between;
-                                align-items: center;
-                                padding: 4px 0;
-                            ",
-                            span { "Titan" }
-                            span { style: "font-weight: 600;", "{titan}" }
+                            style: if bps < 0 { "color: #f59e0b;" } else { "color: #34d399;" },
+                            "{bps:+} bps vs quote"
                         }
                     }
+                    a {
+                        href: "{config::explorer_tx_url(&result.signature)}",
+                        target: "_blank",
+                        style: "color: #60a5fa; font-size: 12px;",
+                        "View transaction"
+                    }
                 }
             }
 
@@ -0,0 +1,101 @@
+use dioxus::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::config::rpc_url;
+use crate::token_registry::{resolve_mint, Token, TokenRegistry};
+
+#[component]
+pub fn TokenPicker(
+    registry: Signal<TokenRegistry>,
+    on_select: EventHandler<Token>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut query = use_signal(String::new);
+    let mut resolving = use_signal(|| false);
+    let mut error_message = use_signal(|| Option::<String>::None);
+
+    let results = registry.read().search(&query.read());
+    let recent = registry.read().recent().to_vec();
+    let q = query.read().trim().to_string();
+    let is_custom_mint = results.is_empty() && Pubkey::from_str(&q).is_ok();
+
+    let add_custom = move |_| {
+        let mint = query.read().trim().to_string();
+        resolving.set(true);
+        error_message.set(None);
+        spawn(async move {
+            let current = registry.read().clone();
+            match resolve_mint(&current, &rpc_url(), &mint).await {
+                Ok(token) => on_select.call(token),
+                Err(e) => error_message.set(Some(e)),
+            }
+            resolving.set(false);
+        });
+    };
+
+    rsx! {
+        div { style: "background: #0b1220; border: 1px solid #334155; border-radius: 12px; padding: 12px; margin-bottom: 12px; display: grid; gap: 8px;",
+            div { style: "display: flex; justify-content: space-between; align-items: center;",
+                span { style: "color: #e0e0e0; font-weight: 600;", "Select a token" }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    style: "background: transparent; color: #94a3b8; border: none; cursor: pointer; font-size: 16px;",
+                    "×"
+                }
+            }
+            input {
+                r#type: "text",
+                value: "{query}",
+                oninput: move |e| query.set(e.value()),
+                placeholder: "Search name, symbol or paste a mint",
+                style: "width: 100%; padding: 8px; border-radius: 8px; border: 1px solid #334155; background: #0f172a; color: white;",
+            }
+            if !recent.is_empty() && q.is_empty() {
+                div { style: "display: flex; flex-wrap: wrap; gap: 6px;",
+                    for token in recent {
+                        button {
+                            key: "recent-{token.mint}",
+                            onclick: {
+                                let token = token.clone();
+                                move |_| on_select.call(token.clone())
+                            },
+                            style: "background: #1e293b; color: #e0e0e0; border: 1px solid #334155; border-radius: 999px; padding: 2px 10px; font-size: 12px; cursor: pointer;",
+                            "{token.symbol}"
+                        }
+                    }
+                }
+            }
+            div { style: "max-height: 240px; overflow-y: auto; display: grid; gap: 2px;",
+                for token in results {
+                    button {
+                        key: "{token.mint}",
+                        onclick: {
+                            let token = token.clone();
+                            move |_| on_select.call(token.clone())
+                        },
+                        style: "display: flex; align-items: center; gap: 8px; background: transparent; border: none; color: #e0e0e0; padding: 6px; cursor: pointer; text-align: left;",
+                        if let Some(ref logo) = token.logo_uri {
+                            img { src: "{logo}", width: "20", height: "20", style: "border-radius: 50%;" }
+                        } else {
+                            div { style: "width: 20px; height: 20px; border-radius: 50%; background: #334155;" }
+                        }
+                        span { style: "font-weight: 600;", "{token.symbol}" }
+                        span { style: "color: #64748b; font-size: 12px;", "{token.name}" }
+                    }
+                }
+            }
+            if is_custom_mint {
+                button {
+                    disabled: *resolving.read(),
+                    onclick: add_custom,
+                    style: "background: #334155; color: white; border: 1px solid #475569; border-radius: 8px; padding: 8px; cursor: pointer;",
+                    if *resolving.read() { "Looking up mint..." } else { "Use custom mint" }
+                }
+            }
+            if let Some(ref err) = *error_message.read() {
+                div { style: "color: #ef4444; font-size: 12px;", "{err}" }
+            }
+        }
+    }
+}
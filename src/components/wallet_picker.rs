@@ -0,0 +1,66 @@
+use dioxus::prelude::*;
+
+use crate::wallet::WalletChoice;
+use crate::wallet_context::use_wallet;
+
+/// Lists discovered wallets and connects the one picked as the app's wallet.
+#[component]
+pub fn WalletPicker(on_connected: EventHandler<String>, on_close: EventHandler<()>) -> Element {
+    let wallet = use_wallet();
+    let choices = WalletChoice::available();
+    let mut connecting = use_signal(|| Option::<String>::None);
+    let mut error_message = use_signal(|| Option::<String>::None);
+
+    let mut pick = move |choice: WalletChoice| {
+        connecting.set(Some(choice.name()));
+        error_message.set(None);
+        spawn(async move {
+            match wallet.connect(choice).await {
+                Ok(address) => on_connected.call(address),
+                Err(e) => error_message.set(Some(format!("Connect failed: {}", e))),
+            }
+            connecting.set(None);
+        });
+    };
+
+    rsx! {
+        div { style: "background: #0b1220; border: 1px solid #334155; border-radius: 12px; padding: 12px; margin-bottom: 16px; display: grid; gap: 8px;",
+            div { style: "display: flex; justify-content: space-between; align-items: center;",
+                span { style: "color: #e0e0e0; font-weight: 600;", "Select a wallet" }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    style: "background: transparent; color: #94a3b8; border: none; cursor: pointer; font-size: 16px;",
+                    "×"
+                }
+            }
+            if choices.is_empty() {
+                div { style: "color: #94a3b8; font-size: 12px;",
+                    "No Solana wallet found. Install Phantom, Solflare or Backpack, or open this page in a wallet's browser."
+                }
+            }
+            for choice in choices {
+                button {
+                    key: "{choice.name()}",
+                    disabled: connecting.read().is_some(),
+                    onclick: {
+                        let choice = choice.clone();
+                        move |_| pick(choice.clone())
+                    },
+                    style: "display: flex; align-items: center; gap: 10px; background: #0f172a; border: 1px solid #334155; border-radius: 10px; color: #e0e0e0; padding: 10px; cursor: pointer; text-align: left;",
+                    if let Some(icon) = choice.icon() {
+                        img { src: "{icon}", width: "24", height: "24", style: "border-radius: 6px;" }
+                    } else {
+                        div { style: "width: 24px; height: 24px; border-radius: 6px; background: #334155;" }
+                    }
+                    span { style: "flex: 1; font-weight: 600;", "{choice.name()}" }
+                    if connecting.read().as_deref() == Some(choice.name().as_str()) {
+                        span { style: "color: #94a3b8; font-size: 12px;", "Connecting..." }
+                    }
+                }
+            }
+            if let Some(ref err) = *error_message.read() {
+                div { style: "color: #ef4444; font-size: 12px;", "{err}" }
+            }
+        }
+    }
+}
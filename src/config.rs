@@ -0,0 +1,277 @@
+// Runtime configuration: endpoints, API keys, and the program, mint and RPC
+// for each cluster. Defaults come from build-time env (`TD_*`) and are
+// overridden by an optional `config.json` served next to the app, so forks
+// and staging deployments only need a different file.
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+use crate::payment_request::percent_encode;
+use crate::storage::Key;
+use crate::timed_delegation::{DEFAULT_PROGRAM_ID, USDC_MINT};
+
+const CONFIG_PATH: &str = "config.json";
+const CLUSTER: Key<Cluster> = Key::new("td_cluster", 1);
+const DEVNET_USDC_MINT: &str = "4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU";
+
+macro_rules! env_or {
+    ($name:literal, $default:expr) => {
+        option_env!($name).unwrap_or($default).to_string()
+    };
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Devnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub const ALL: [Cluster; 3] = [Cluster::Mainnet, Cluster::Devnet, Cluster::Localnet];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "Mainnet",
+            Cluster::Devnet => "Devnet",
+            Cluster::Localnet => "Localnet",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "mainnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Localnet => "localnet",
+        }
+    }
+
+    /// Wallet Standard chain identifier.
+    pub fn chain_id(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "solana:mainnet",
+            Cluster::Devnet => "solana:devnet",
+            Cluster::Localnet => "solana:localnet",
+        }
+    }
+
+    /// Jupiter, Dflow and Titan only quote mainnet liquidity.
+    pub fn has_aggregators(&self) -> bool {
+        *self == Cluster::Mainnet
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" => Some(Cluster::Mainnet),
+            "devnet" => Some(Cluster::Devnet),
+            "localnet" | "localhost" => Some(Cluster::Localnet),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClusterProfile {
+    pub rpc_url: String,
+    pub program_id: String,
+    pub usdc_mint: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EndpointConfig {
+    pub jupiter_ultra_api: String,
+    pub jupiter_swap_api: String,
+    /// Sent as `x-api-key` when set. Never committed to the repo.
+    pub jupiter_api_key: Option<String>,
+    pub dflow_quote_url: String,
+    pub titan_quote_url: String,
+    /// Server running `solana_pay::TransactionRequestHandler`. When set,
+    /// shared payment requests also get a Solana Pay QR.
+    #[serde(default)]
+    pub transaction_request_url: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub cluster: Cluster,
+    pub endpoints: EndpointConfig,
+    pub mainnet: ClusterProfile,
+    pub devnet: ClusterProfile,
+    pub localnet: ClusterProfile,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl AppConfig {
+    /// Build-time defaults; any `TD_*` variable set during `dx build`
+    /// replaces the corresponding value.
+    pub fn from_env() -> Self {
+        Self {
+            cluster: option_env!("TD_CLUSTER")
+                .and_then(Cluster::parse)
+                .unwrap_or_default(),
+            endpoints: EndpointConfig {
+                jupiter_ultra_api: env_or!("TD_JUPITER_ULTRA_API", "https://api.jup.ag/ultra/v1"),
+                jupiter_swap_api: env_or!("TD_JUPITER_SWAP_API", "https://api.jup.ag/swap/v1"),
+                jupiter_api_key: option_env!("TD_JUPITER_API_KEY").map(str::to_string),
+                dflow_quote_url: env_or!(
+                    "TD_DFLOW_QUOTE_URL",
+                    "https://hermes-titan-proxy.fly.dev/api/dflow/quote"
+                ),
+                titan_quote_url: env_or!(
+                    "TD_TITAN_QUOTE_URL",
+                    "https://hermes-titan-proxy.fly.dev/api/titan/quote"
+                ),
+                transaction_request_url: option_env!("TD_TRANSACTION_REQUEST_URL").map(str::to_string),
+            },
+            mainnet: ClusterProfile {
+                rpc_url: env_or!(
+                    "TD_MAINNET_RPC_URL",
+                    "https://johna-k3cr1v-fast-mainnet.helius-rpc.com"
+                ),
+                program_id: env_or!("TD_MAINNET_PROGRAM_ID", DEFAULT_PROGRAM_ID),
+                usdc_mint: env_or!("TD_MAINNET_USDC_MINT", USDC_MINT),
+            },
+            devnet: ClusterProfile {
+                rpc_url: env_or!("TD_DEVNET_RPC_URL", "https://api.devnet.solana.com"),
+                program_id: env_or!("TD_DEVNET_PROGRAM_ID", DEFAULT_PROGRAM_ID),
+                usdc_mint: env_or!("TD_DEVNET_USDC_MINT", DEVNET_USDC_MINT),
+            },
+            localnet: ClusterProfile {
+                rpc_url: env_or!("TD_LOCALNET_RPC_URL", "http://127.0.0.1:8899"),
+                program_id: env_or!("TD_LOCALNET_PROGRAM_ID", DEFAULT_PROGRAM_ID),
+                usdc_mint: env_or!("TD_LOCALNET_USDC_MINT", USDC_MINT),
+            },
+        }
+    }
+
+    pub fn profile(&self, cluster: Cluster) -> &ClusterProfile {
+        match cluster {
+            Cluster::Mainnet => &self.mainnet,
+            Cluster::Devnet => &self.devnet,
+            Cluster::Localnet => &self.localnet,
+        }
+    }
+
+    pub fn active(&self) -> &ClusterProfile {
+        self.profile(self.cluster)
+    }
+
+    /// Applies a partial JSON document on top of this config; keys that are
+    /// absent keep their current value.
+    pub fn merge_json(&self, overlay: serde_json::Value) -> Result<Self, String> {
+        let mut base = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        merge(&mut base, overlay);
+        serde_json::from_value(base).map_err(|e| format!("invalid config: {}", e))
+    }
+}
+
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<AppConfig> = RefCell::new(AppConfig::from_env());
+}
+
+pub fn set_current(config: AppConfig) {
+    CURRENT.with(|c| *c.borrow_mut() = config);
+}
+
+pub fn cluster() -> Cluster {
+    CURRENT.with(|c| c.borrow().cluster)
+}
+
+/// Switches the active profile and remembers the choice for the next load.
+pub fn set_cluster(cluster: Cluster) {
+    CURRENT.with(|c| c.borrow_mut().cluster = cluster);
+    CLUSTER.save(&cluster);
+}
+
+pub fn rpc_url() -> String {
+    CURRENT.with(|c| c.borrow().active().rpc_url.clone())
+}
+
+pub fn program_id() -> String {
+    CURRENT.with(|c| c.borrow().active().program_id.clone())
+}
+
+pub fn usdc_mint() -> String {
+    CURRENT.with(|c| c.borrow().active().usdc_mint.clone())
+}
+
+pub fn endpoints() -> EndpointConfig {
+    CURRENT.with(|c| c.borrow().endpoints.clone())
+}
+
+fn explorer_url(path: &str) -> String {
+    let base = format!("https://solscan.io/{}", path);
+    match cluster() {
+        Cluster::Mainnet => base,
+        Cluster::Devnet => format!("{}?cluster=devnet", base),
+        // Solscan cannot reach a local validator; the Solana explorer can.
+        Cluster::Localnet => format!(
+            "https://explorer.solana.com/{}?cluster=custom&customUrl={}",
+            path,
+            percent_encode(&rpc_url())
+        ),
+    }
+}
+
+pub fn explorer_tx_url(signature: &str) -> String {
+    explorer_url(&format!("tx/{}", signature))
+}
+
+pub fn explorer_address_url(address: &str) -> String {
+    explorer_url(&format!("address/{}", address))
+}
+
+/// Env defaults overlaid with the served `config.json`, if there is one. A
+/// cluster picked in the UI wins over both, and a handoff from another
+/// browser wins over that.
+pub async fn load() -> AppConfig {
+    let mut config = load_served().await;
+    let handoff_cluster = crate::handoff::pending().and_then(|s| Cluster::parse(&s.cluster));
+    if let Some(cluster) = handoff_cluster.or_else(|| CLUSTER.load()) {
+        config.cluster = cluster;
+    }
+    config
+}
+
+async fn load_served() -> AppConfig {
+    let defaults = AppConfig::from_env();
+    let response = match Request::get(CONFIG_PATH).send().await {
+        Ok(r) if r.ok() => r,
+        _ => {
+            log::info!("No {} served, using build-time config", CONFIG_PATH);
+            return defaults;
+        }
+    };
+    let overlay = match response.json::<serde_json::Value>().await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Failed to parse {}: {:?}", CONFIG_PATH, e);
+            return defaults;
+        }
+    };
+    match defaults.merge_json(overlay) {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("{}", e);
+            defaults
+        }
+    }
+}
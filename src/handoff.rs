@@ -0,0 +1,191 @@
+// Moving a half-finished flow between a wallet's in-app browser and the
+// system browser. In-app browsers block passkeys, so the user registers the
+// passkey or signs a voucher in Safari/Chrome and comes back to the wallet to
+// sign the transaction. Form state rides along in the URL fragment, which is
+// never sent to the server, and is cleared from the address bar once read.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as B64URL;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::payment_request::percent_encode;
+use crate::storage::DelegationDraft;
+use crate::wallet_standard::{self, StandardWallet};
+
+const FRAGMENT_KEY: &str = "handoff=";
+/// Name the Solana Mobile wallet-standard shim registers under on Android.
+const MWA_WALLET_NAME: &str = "Mobile Wallet Adapter";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HandoffState {
+    pub cluster: String,
+    pub draft: DelegationDraft,
+    pub passkey_pubkey_b64: String,
+    pub passkey_cred_id_b64: String,
+    /// Encoded `tdv1:` voucher, if one was signed or imported.
+    pub voucher: Option<String>,
+    /// Wallet whose in-app browser started the handoff.
+    pub from_wallet: Option<String>,
+}
+
+impl HandoffState {
+    pub fn encode(&self) -> Result<String, String> {
+        let bytes = rmp_serde::to_vec_named(self).map_err(|e| e.to_string())?;
+        Ok(B64URL.encode(bytes))
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let bytes = B64URL.decode(text.trim().as_bytes()).map_err(|e| e.to_string())?;
+        rmp_serde::from_slice(&bytes).map_err(|e| format!("invalid handoff state: {}", e))
+    }
+
+    /// This page (payment-request query included) with the state attached.
+    pub fn url(&self) -> Result<String, String> {
+        Ok(format!("{}#{}{}", current_page_url()?, FRAGMENT_KEY, self.encode()?))
+    }
+}
+
+/// Wallets whose universal links reopen a URL in their in-app browser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MobileWallet {
+    Phantom,
+    Solflare,
+    Backpack,
+}
+
+impl MobileWallet {
+    pub const ALL: [MobileWallet; 3] = [MobileWallet::Phantom, MobileWallet::Solflare, MobileWallet::Backpack];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MobileWallet::Phantom => "Phantom",
+            MobileWallet::Solflare => "Solflare",
+            MobileWallet::Backpack => "Backpack",
+        }
+    }
+
+    /// Matches a Wallet Standard name or a user-agent fragment.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|w| name.contains(&w.label().to_lowercase()))
+    }
+
+    pub fn browse_url(&self, target: &str, referrer: &str) -> String {
+        let base = match self {
+            MobileWallet::Phantom => "https://phantom.app/ul/browse",
+            MobileWallet::Solflare => "https://solflare.com/ul/v1/browse",
+            MobileWallet::Backpack => "https://backpack.app/ul/v1/browse",
+        };
+        format!("{}/{}?ref={}", base, percent_encode(target), percent_encode(referrer))
+    }
+}
+
+fn current_page_url() -> Result<String, String> {
+    let location = web_sys::window().ok_or("window not available")?.location();
+    let origin = location.origin().map_err(|_| "page origin unavailable")?;
+    let path = location.pathname().unwrap_or_default();
+    let search = location.search().unwrap_or_default();
+    Ok(format!("{}{}{}", origin, path, search))
+}
+
+pub fn page_origin() -> String {
+    web_sys::window()
+        .and_then(|w| w.location().origin().ok())
+        .unwrap_or_default()
+}
+
+/// Handoff state in the address bar, without consuming it.
+pub fn pending() -> Option<HandoffState> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let encoded = hash.trim_start_matches('#').strip_prefix(FRAGMENT_KEY)?;
+    match HandoffState::decode(encoded) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            log::warn!("Ignoring handoff state: {}", e);
+            None
+        }
+    }
+}
+
+/// Reads the handoff state and strips it from the address bar so a reload
+/// or shared link does not replay it.
+pub fn take() -> Option<HandoffState> {
+    let state = pending()?;
+    if let (Some(window), Ok(url)) = (web_sys::window(), current_page_url()) {
+        if let Ok(history) = window.history() {
+            let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+        }
+    }
+    Some(state)
+}
+
+/// The Mobile Wallet Adapter entry, when the browser exposes one (Chrome on
+/// Android with a compatible wallet installed).
+pub fn mobile_wallet_adapter() -> Option<StandardWallet> {
+    wallet_standard::wallets()
+        .into_iter()
+        .find(|w| w.name == MWA_WALLET_NAME)
+}
+
+/// Best guess at the wallet app hosting this in-app browser.
+pub fn detect_in_app_wallet() -> Option<MobileWallet> {
+    let ua = web_sys::window()?.navigator().user_agent().ok()?;
+    MobileWallet::from_name(&ua)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips() {
+        let state = HandoffState {
+            cluster: "devnet".to_string(),
+            draft: DelegationDraft {
+                mode: "withdraw".to_string(),
+                withdraw_delegator: "Dele111111111111111111111111111111111111111".to_string(),
+                ..Default::default()
+            },
+            passkey_pubkey_b64: "AqvS".to_string(),
+            passkey_cred_id_b64: "Y3JlZA".to_string(),
+            voucher: Some("tdv1:abc".to_string()),
+            from_wallet: Some("Phantom".to_string()),
+        };
+        let encoded = state.encode().unwrap();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(HandoffState::decode(&encoded).unwrap(), state);
+        assert_eq!(HandoffState::decode(&format!(" {}\n", encoded)).unwrap(), state);
+    }
+
+    #[test]
+    fn rejects_corrupt_state() {
+        assert!(HandoffState::decode("not base64!").is_err());
+        assert!(HandoffState::decode("AAAA").is_err());
+    }
+
+    #[test]
+    fn recognizes_wallets_by_name() {
+        assert_eq!(MobileWallet::from_name("Solflare"), Some(MobileWallet::Solflare));
+        assert_eq!(
+            MobileWallet::from_name("Mozilla/5.0 (iPhone) Phantom/ios"),
+            Some(MobileWallet::Phantom)
+        );
+        assert_eq!(MobileWallet::from_name("Safari"), None);
+    }
+
+    #[test]
+    fn browse_urls_encode_the_target() {
+        let url = MobileWallet::Phantom.browse_url(
+            "https://app.example.com/?pr=1#handoff=x",
+            "https://app.example.com",
+        );
+        assert_eq!(
+            url,
+            "https://phantom.app/ul/browse/https%3A%2F%2Fapp.example.com%2F%3Fpr%3D1%23handoff%3Dx?ref=https%3A%2F%2Fapp.example.com"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+// Per-delegation pull history rebuilt from on-chain withdraw transactions.
+use futures::future::join_all;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::rpc::{
+    get_account_data_base64, get_signatures_for_address, get_transaction, TokenBalance,
+    TransactionInfo, TransactionMeta,
+};
+use crate::timed_delegation::{decode_base64, derive_addresses, parse_delegation_state};
+
+/// Withdraw instruction tag in the delegation program.
+const WITHDRAW_TAG: u8 = 3;
+/// Index of the destination token account in the withdraw instruction's accounts.
+const WITHDRAW_DESTINATION_INDEX: usize = 5;
+/// Optional trailing fee-recipient token account.
+const WITHDRAW_FEE_INDEX: usize = 10;
+const HISTORY_LIMIT: u32 = 100;
+/// `getTransaction` calls in flight at once; public RPCs rate-limit bursts.
+const FETCH_CONCURRENCY: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LedgerEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub amount: u64,
+    pub auth_expiry_ts: i64,
+    /// Nonce consumed by this pull. Not part of the instruction data; derived
+    /// from the delegation's current nonce and the order of successful pulls.
+    pub nonce: Option<u64>,
+    /// Destination token balance change observed in the transaction.
+    pub received: Option<u64>,
+    /// Fee-recipient balance change, for pulls made while a fee was charged.
+    pub protocol_fee: Option<u64>,
+    pub fee_lamports: u64,
+    pub failed: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Ledger {
+    pub delegation: String,
+    pub decimals: u8,
+    pub entries: Vec<LedgerEntry>,
+    /// Sum of gross pull amounts.
+    pub total_withdrawn: u64,
+    /// Sum of destination balance increases.
+    pub total_received: u64,
+    pub total_fees: u64,
+    /// Allowance still approved to the delegate PDA on the source account.
+    pub remaining_allowance: Option<u64>,
+}
+
+impl Ledger {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "signature,slot,block_time,amount,protocol_fee,received,auth_expiry_ts,nonce,fee_lamports,status\n",
+        );
+        for e in &self.entries {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                e.signature,
+                e.slot,
+                e.block_time.map(|t| t.to_string()).unwrap_or_default(),
+                e.amount,
+                e.protocol_fee.map(|f| f.to_string()).unwrap_or_default(),
+                e.received.map(|r| r.to_string()).unwrap_or_default(),
+                e.auth_expiry_ts,
+                e.nonce.map(|n| n.to_string()).unwrap_or_default(),
+                e.fee_lamports,
+                if e.failed { "failed" } else { "ok" },
+            ));
+        }
+        out
+    }
+}
+
+/// Decodes withdraw instruction data into `(amount, auth_expiry_ts)`.
+pub fn parse_withdraw_data(data: &[u8]) -> Option<(u64, i64)> {
+    if data.len() < 17 || data[0] != WITHDRAW_TAG {
+        return None;
+    }
+    let amount = u64::from_le_bytes(data[1..9].try_into().ok()?);
+    let expiry = i64::from_le_bytes(data[9..17].try_into().ok()?);
+    Some((amount, expiry))
+}
+
+/// Reads `delegated_amount` from an SPL token account if it is delegated to
+/// `delegate`.
+pub fn parse_delegated_amount(data: &[u8], delegate: &Pubkey) -> Result<u64, String> {
+    if data.len() < 129 {
+        return Err("token account data too small".to_string());
+    }
+    let has_delegate = u32::from_le_bytes(data[72..76].try_into().unwrap()) == 1;
+    if !has_delegate || &data[76..108] != delegate.as_ref() {
+        return Ok(0);
+    }
+    Ok(u64::from_le_bytes(data[121..129].try_into().unwrap()))
+}
+
+/// Extracts the withdraw made by `program_id` in `tx`, joined with the
+/// destination token balance delta.
+pub fn entry_from_transaction(
+    signature: &str,
+    tx: &TransactionInfo,
+    program_id: &Pubkey,
+) -> Option<LedgerEntry> {
+    let program_id = program_id.to_string();
+    let keys = &tx.transaction.message.account_keys;
+    let ix = tx.transaction.message.instructions.iter().find(|ix| {
+        keys.get(ix.program_id_index) == Some(&program_id)
+            && bs58::decode(&ix.data)
+                .into_vec()
+                .ok()
+                .and_then(|d| parse_withdraw_data(&d))
+                .is_some()
+    })?;
+    let data = bs58::decode(&ix.data).into_vec().ok()?;
+    let (amount, auth_expiry_ts) = parse_withdraw_data(&data)?;
+
+    let meta = tx.meta.as_ref();
+    let received = ix
+        .accounts
+        .get(WITHDRAW_DESTINATION_INDEX)
+        .and_then(|&dest| meta.and_then(|m| token_delta(m, dest)));
+    let protocol_fee = ix
+        .accounts
+        .get(WITHDRAW_FEE_INDEX)
+        .and_then(|&fee| meta.and_then(|m| token_delta(m, fee)));
+
+    Some(LedgerEntry {
+        signature: signature.to_string(),
+        slot: tx.slot,
+        block_time: tx.block_time,
+        amount,
+        auth_expiry_ts,
+        nonce: None,
+        received,
+        protocol_fee,
+        fee_lamports: meta.map(|m| m.fee).unwrap_or(0),
+        failed: meta.map(|m| m.err.is_some()).unwrap_or(false),
+    })
+}
+
+fn token_delta(meta: &TransactionMeta, account_index: usize) -> Option<u64> {
+    let balance = |list: &[TokenBalance]| {
+        list.iter()
+            .find(|b| b.account_index == account_index)
+            .and_then(|b| b.ui_token_amount.amount.parse::<u64>().ok())
+    };
+    let post = balance(&meta.post_token_balances)?;
+    let pre = balance(&meta.pre_token_balances).unwrap_or(0);
+    Some(post.saturating_sub(pre))
+}
+
+/// Assigns consumed nonces newest-first, given the delegation's current nonce.
+pub fn assign_nonces(entries: &mut [LedgerEntry], current_nonce: u64) {
+    let mut next = current_nonce;
+    for entry in entries.iter_mut().filter(|e| !e.failed) {
+        if next == 0 {
+            break;
+        }
+        next -= 1;
+        entry.nonce = Some(next);
+    }
+}
+
+pub async fn fetch_ledger(
+    rpc_url: &str,
+    program_id: &Pubkey,
+    delegator: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    decimals: u8,
+) -> Result<Ledger, String> {
+    let addrs = derive_addresses(program_id, delegator, beneficiary, mint);
+    let delegation = addrs.delegation_pda.to_string();
+
+    let state_b64 = get_account_data_base64(rpc_url, &delegation).await?;
+    let state = parse_delegation_state(&decode_base64(&state_b64)?)?;
+
+    let signatures = get_signatures_for_address(rpc_url, &delegation, HISTORY_LIMIT, None).await?;
+    let mut entries = Vec::new();
+    for batch in signatures.chunks(FETCH_CONCURRENCY) {
+        let txs = join_all(batch.iter().map(|info| get_transaction(rpc_url, &info.signature))).await;
+        for (info, tx) in batch.iter().zip(txs) {
+            match tx {
+                Ok(tx) => {
+                    if let Some(entry) = entry_from_transaction(&info.signature, &tx, program_id) {
+                        entries.push(entry);
+                    }
+                }
+                Err(e) => log::warn!("Skipping {}: {}", info.signature, e),
+            }
+        }
+    }
+    assign_nonces(&mut entries, state.nonce);
+
+    // Gross pulls are the allowance actually used; `received` is only the
+    // destination's increase, which misses fees and pull-and-swap proceeds.
+    let total_withdrawn = entries.iter().filter(|e| !e.failed).map(|e| e.amount).sum();
+    let total_received = entries
+        .iter()
+        .filter(|e| !e.failed)
+        .map(|e| e.received.unwrap_or(e.amount))
+        .sum();
+
+    let total_fees = entries
+        .iter()
+        .filter(|e| !e.failed)
+        .filter_map(|e| e.protocol_fee)
+        .sum();
+
+    let source_ata = addrs.source_ata.to_string();
+    let remaining_allowance = match get_account_data_base64(rpc_url, &source_ata).await {
+        Ok(b64) => Some(parse_delegated_amount(
+            &decode_base64(&b64)?,
+            &addrs.delegate_pda,
+        )?),
+        Err(_) => None,
+    };
+
+    Ok(Ledger {
+        delegation,
+        decimals,
+        entries,
+        total_withdrawn,
+        total_received,
+        total_fees,
+        remaining_allowance,
+    })
+}
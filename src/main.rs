@@ -1,24 +1,65 @@
 use dioxus::prelude::*;
 
 mod api;
+mod config;
+mod storage;
 mod wallet;
+mod wallet_standard;
+mod wallet_context;
+mod siws;
+mod handoff;
 mod components;
 mod timed_delegation;
 mod rpc;
+mod payment_request;
+mod solana_pay;
+mod qr;
+mod voucher;
+mod ledger;
+mod token_registry;
+mod balances;
+mod v0_transaction;
+mod swap_delegate;
+mod pull_swap;
+mod program_config;
 
-use components::DelegationModal;
+use components::{ClusterSelector, DelegationModal, SwapModal};
 
 fn main() {
     dioxus_logger::init(dioxus_logger::tracing::Level::INFO).expect("failed to init logger");
+    wallet_standard::start_discovery();
     launch(App);
 }
 
 #[component]
 fn App() -> Element {
+    // Endpoints and cluster profiles must be in place before any RPC call.
+    wallet_context::use_wallet_provider();
+    let mut cluster = use_signal(config::cluster);
+    let loaded = use_resource(move || async move {
+        config::set_current(config::load().await);
+        cluster.set(config::cluster());
+    });
+
     rsx! {
         div {
-            style: "width: 100%; height: 100vh; display: flex; align-items: center; justify-content: center; background: #0f172a;",
-            DelegationModal {}
+            style: "width: 100%; min-height: 100vh; padding: 24px 0; display: flex; align-items: center; justify-content: center; background: #0f172a;",
+            if loaded.read().is_some() {
+                div {
+                    ClusterSelector { cluster }
+                    // Keyed on the cluster so balances, ledgers, quotes and
+                    // inputs from the previous cluster are dropped on switch.
+                    for active in [*cluster.read()] {
+                        div { key: "{active.as_str()}", style: "display: flex; flex-direction: column; gap: 24px;",
+                            DelegationModal {}
+                            // Aggregators only quote mainnet liquidity.
+                            if active.has_aggregators() {
+                                SwapModal {}
+                            }
+                        }
+                    }
+                }
+            }
         }
     }
 }
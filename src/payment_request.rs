@@ -0,0 +1,274 @@
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Query parameter that marks a URL as a delegation payment request.
+pub const PAYMENT_REQUEST_MARKER: &str = "pr";
+pub const PAYMENT_REQUEST_VERSION: &str = "1";
+
+/// A merchant's request for a timed delegation, carried in the app URL's
+/// query string, e.g.
+/// `https://pull.unruggable.io/?pr=1&beneficiary=<pubkey>&amount=10&duration=720&label=Acme`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaymentRequest {
+    pub beneficiary: String,
+    pub mint: Option<String>,
+    /// Allowance cap in UI units of the mint, e.g. `"10.5"`.
+    pub amount: Option<String>,
+    pub duration_hours: Option<i64>,
+    pub label: Option<String>,
+    pub memo: Option<String>,
+    pub return_url: Option<String>,
+}
+
+impl PaymentRequest {
+    pub fn new(beneficiary: &str) -> Self {
+        Self {
+            beneficiary: beneficiary.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses a full URL or a bare query string (with or without `?`).
+    /// Returns `Ok(None)` when the query is not a payment request.
+    pub fn parse(url_or_query: &str) -> Result<Option<Self>, String> {
+        let query = match url_or_query.split_once('?') {
+            Some((_, q)) => q,
+            None if url_or_query.contains("://") => return Ok(None),
+            None => url_or_query,
+        };
+        let query = query.split('#').next().unwrap_or("");
+
+        let mut marker = None;
+        let mut req = PaymentRequest::default();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                PAYMENT_REQUEST_MARKER => marker = Some(value),
+                "beneficiary" => req.beneficiary = value,
+                "mint" => req.mint = non_empty(value),
+                "amount" => req.amount = non_empty(value),
+                "duration" => {
+                    req.duration_hours = Some(value.parse().map_err(|_| "invalid duration")?)
+                }
+                "label" => req.label = non_empty(value),
+                "memo" => req.memo = non_empty(value),
+                "return" => req.return_url = non_empty(value),
+                _ => {}
+            }
+        }
+
+        match marker.as_deref() {
+            None => return Ok(None),
+            Some(PAYMENT_REQUEST_VERSION) => {}
+            Some(v) => return Err(format!("unsupported payment request version {}", v)),
+        }
+        req.validate()?;
+        Ok(Some(req))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        Pubkey::from_str(self.beneficiary.trim()).map_err(|_| "invalid beneficiary pubkey")?;
+        if let Some(mint) = &self.mint {
+            Pubkey::from_str(mint.trim()).map_err(|_| "invalid mint pubkey")?;
+        }
+        if let Some(amount) = &self.amount {
+            let valid = amount.chars().any(|c| c.is_ascii_digit())
+                && amount.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && amount.matches('.').count() <= 1;
+            if !valid {
+                return Err("invalid amount".to_string());
+            }
+        }
+        if let Some(hours) = self.duration_hours {
+            if hours <= 0 {
+                return Err("duration must be positive".to_string());
+            }
+        }
+        if let Some(url) = &self.return_url {
+            let local = url
+                .strip_prefix("http://")
+                .is_some_and(is_local_host);
+            if !(url.starts_with("https://") || local) {
+                return Err("return URL must be https".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Serializes into the query string understood by [`PaymentRequest::parse`].
+    pub fn to_query(&self) -> String {
+        let mut out = format!("{}={}", PAYMENT_REQUEST_MARKER, PAYMENT_REQUEST_VERSION);
+        push_param(&mut out, "beneficiary", Some(&self.beneficiary));
+        push_param(&mut out, "mint", self.mint.as_ref());
+        push_param(&mut out, "amount", self.amount.as_ref());
+        push_param(&mut out, "duration", self.duration_hours.map(|h| h.to_string()).as_ref());
+        push_param(&mut out, "label", self.label.as_ref());
+        push_param(&mut out, "memo", self.memo.as_ref());
+        push_param(&mut out, "return", self.return_url.as_ref());
+        out
+    }
+
+    pub fn to_url(&self, base_url: &str) -> String {
+        let base = base_url.split('?').next().unwrap_or(base_url);
+        format!("{}?{}", base, self.to_query())
+    }
+}
+
+/// Returns the decoded value of `key` from a URL's query string.
+pub fn query_param(url_or_query: &str, key: &str) -> Option<String> {
+    let query = url_or_query.split_once('?').map_or(url_or_query, |(_, q)| q);
+    let query = query.split('#').next().unwrap_or("");
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, v)| percent_decode(v).ok())
+}
+
+/// Plain http is only allowed for local development servers.
+fn is_local_host(authority_and_path: &str) -> bool {
+    let authority = authority_and_path
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or("");
+    // Userinfo would let `localhost:80@evil.example` pass as local.
+    if authority.contains('@') {
+        return false;
+    }
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => authority.split(':').next().unwrap_or(""),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+fn push_param(out: &mut String, key: &str, value: Option<&String>) {
+    if let Some(value) = value {
+        out.push('&');
+        out.push_str(key);
+        out.push('=');
+        out.push_str(&percent_encode(value));
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+pub fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for &b in value.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+pub fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or("invalid percent-encoding")?;
+                out.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| "invalid utf-8 in query".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BENEFICIARY: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+
+    fn request() -> PaymentRequest {
+        PaymentRequest {
+            amount: Some("10.5".to_string()),
+            duration_hours: Some(720),
+            label: Some("Acme & Co".to_string()),
+            return_url: Some("https://acme.example/done?order=1".to_string()),
+            ..PaymentRequest::new(BENEFICIARY)
+        }
+    }
+
+    #[test]
+    fn round_trips_through_url() {
+        let req = request();
+        let url = req.to_url("https://pull.unruggable.io/?old=1");
+        assert!(url.starts_with("https://pull.unruggable.io/?pr=1&"));
+        assert_eq!(PaymentRequest::parse(&url).unwrap(), Some(req));
+    }
+
+    #[test]
+    fn ignores_urls_without_marker() {
+        assert_eq!(PaymentRequest::parse("https://pull.unruggable.io/").unwrap(), None);
+        let query = format!("beneficiary={}", BENEFICIARY);
+        assert_eq!(PaymentRequest::parse(&query).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let query = format!("pr=2&beneficiary={}", BENEFICIARY);
+        assert!(PaymentRequest::parse(&query).is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_without_digits() {
+        for amount in [".", "..", "1.2.3", "abc", ""] {
+            let req = PaymentRequest {
+                amount: Some(amount.to_string()),
+                ..PaymentRequest::new(BENEFICIARY)
+            };
+            assert!(req.validate().is_err(), "{:?} accepted", amount);
+        }
+    }
+
+    #[test]
+    fn return_url_requires_https_outside_localhost() {
+        let with_return = |url: &str| PaymentRequest {
+            return_url: Some(url.to_string()),
+            ..PaymentRequest::new(BENEFICIARY)
+        };
+        assert!(with_return("https://acme.example/done").validate().is_ok());
+        assert!(with_return("http://localhost:3000/done").validate().is_ok());
+        assert!(with_return("http://127.0.0.1/done").validate().is_ok());
+        assert!(with_return("http://[::1]:8080/").validate().is_ok());
+        assert!(with_return("http://acme.example/done").validate().is_err());
+        assert!(with_return("http://localhost.evil.example/").validate().is_err());
+        assert!(with_return("http://localhost:80@evil.example/done").validate().is_err());
+        assert!(with_return("javascript:alert(1)").validate().is_err());
+    }
+
+    #[test]
+    fn percent_coding_round_trips() {
+        let value = "Acme & Co / 100% ✓";
+        assert_eq!(percent_decode(&percent_encode(value)).unwrap(), value);
+        assert!(percent_decode("%zz").is_err());
+    }
+}
@@ -0,0 +1,266 @@
+// Program-wide settings stored in the `config` PDA. Every delegation and
+// withdraw instruction passes this account; the program rejects both while
+// `paused` is set, so the client checks it up front for a clearer error.
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_sdk_ids::system_program;
+
+use crate::rpc::get_account_data_base64;
+use crate::timed_delegation::{decode_base64, instruction_to_js, JsInstruction};
+
+/// is_initialized (1) + admin (32) + fee_recipient (32) + fee_bps (2) + paused (1)
+pub const CONFIG_ACCOUNT_LEN: usize = 68;
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+const IX_INITIALIZE_CONFIG: u8 = 0;
+const IX_UPDATE_CONFIG: u8 = 1;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgramConfig {
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u16,
+    pub paused: bool,
+}
+
+/// A pull split into what leaves the delegator, the protocol's cut and what
+/// the beneficiary receives.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeBreakdown {
+    pub gross: u64,
+    pub fee: u64,
+    pub net: u64,
+}
+
+impl ProgramConfig {
+    /// Rounds the fee down, as the program does.
+    pub fn fee_for(&self, gross: u64) -> u64 {
+        (gross as u128 * self.fee_bps as u128 / 10_000) as u64
+    }
+
+    pub fn fee_breakdown(&self, gross: u64) -> FeeBreakdown {
+        let fee = self.fee_for(gross);
+        FeeBreakdown {
+            gross,
+            fee,
+            net: gross - fee,
+        }
+    }
+
+    /// Recipient wallet to pass to the withdraw builder, if a fee is charged.
+    pub fn charged_fee_recipient(&self) -> Option<Pubkey> {
+        (self.fee_bps > 0).then_some(self.fee_recipient)
+    }
+}
+
+pub fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], program_id).0
+}
+
+pub fn parse_program_config(data: &[u8]) -> Result<ProgramConfig, String> {
+    if data.len() < CONFIG_ACCOUNT_LEN {
+        return Err("config account data too small".to_string());
+    }
+    if data[0] == 0 {
+        return Err("config account is not initialized".to_string());
+    }
+    let pubkey_at = |offset: usize| {
+        let bytes: [u8; 32] = data[offset..offset + 32].try_into().unwrap();
+        Pubkey::new_from_array(bytes)
+    };
+    Ok(ProgramConfig {
+        admin: pubkey_at(1),
+        fee_recipient: pubkey_at(33),
+        fee_bps: u16::from_le_bytes([data[65], data[66]]),
+        paused: data[67] != 0,
+    })
+}
+
+fn validate(config: &ProgramConfig) -> Result<(), String> {
+    if config.fee_bps > MAX_FEE_BPS {
+        return Err(format!("fee cannot exceed {} bps", MAX_FEE_BPS));
+    }
+    Ok(())
+}
+
+/// One-time setup; the signer becomes the admin and pays for the account.
+pub fn build_initialize_config_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_recipient: &Pubkey,
+    fee_bps: u16,
+) -> Result<JsInstruction, String> {
+    validate(&ProgramConfig {
+        admin: *admin,
+        fee_recipient: *fee_recipient,
+        fee_bps,
+        paused: false,
+    })?;
+
+    let mut data = Vec::with_capacity(1 + 32 + 2);
+    data.push(IX_INITIALIZE_CONFIG);
+    data.extend_from_slice(fee_recipient.as_ref());
+    data.extend_from_slice(&fee_bps.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address(program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    };
+    Ok(instruction_to_js(&ix))
+}
+
+/// Replaces every field; `admin` must sign and `updated.admin` may hand
+/// control to a new key.
+pub fn build_update_config_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    updated: &ProgramConfig,
+) -> Result<JsInstruction, String> {
+    validate(updated)?;
+
+    let mut data = Vec::with_capacity(1 + 32 + 32 + 2 + 1);
+    data.push(IX_UPDATE_CONFIG);
+    data.extend_from_slice(updated.admin.as_ref());
+    data.extend_from_slice(updated.fee_recipient.as_ref());
+    data.extend_from_slice(&updated.fee_bps.to_le_bytes());
+    data.push(updated.paused as u8);
+
+    let ix = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_address(program_id), false),
+        ],
+        data,
+    };
+    Ok(instruction_to_js(&ix))
+}
+
+/// `None` when the program has not been initialized yet.
+pub async fn fetch_program_config(
+    rpc_url: &str,
+    program_id: &Pubkey,
+) -> Result<Option<ProgramConfig>, String> {
+    let address = config_address(program_id).to_string();
+    match get_account_data_base64(rpc_url, &address).await {
+        Ok(data) => parse_program_config(&decode_base64(&data)?).map(Some),
+        Err(e) if e == "account not found" => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn check_not_paused(config: Option<&ProgramConfig>) -> Result<(), String> {
+    match config {
+        Some(config) if config.paused => {
+            Err("The delegation program is paused by its admin. Try again later.".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn ensure_not_paused(rpc_url: &str, program_id: &Pubkey) -> Result<(), String> {
+    check_not_paused(fetch_program_config(rpc_url, program_id).await?.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timed_delegation::instruction_from_js;
+
+    fn config() -> ProgramConfig {
+        ProgramConfig {
+            admin: Pubkey::new_from_array([1; 32]),
+            fee_recipient: Pubkey::new_from_array([2; 32]),
+            fee_bps: 25,
+            paused: true,
+        }
+    }
+
+    fn account_data(config: &ProgramConfig) -> Vec<u8> {
+        let mut data = vec![1];
+        data.extend_from_slice(config.admin.as_ref());
+        data.extend_from_slice(config.fee_recipient.as_ref());
+        data.extend_from_slice(&config.fee_bps.to_le_bytes());
+        data.push(config.paused as u8);
+        data
+    }
+
+    #[test]
+    fn parses_config_account() {
+        let data = account_data(&config());
+        assert_eq!(data.len(), CONFIG_ACCOUNT_LEN);
+        assert_eq!(parse_program_config(&data).unwrap(), config());
+    }
+
+    #[test]
+    fn rejects_short_or_uninitialized_accounts() {
+        let mut data = account_data(&config());
+        assert!(parse_program_config(&data[..CONFIG_ACCOUNT_LEN - 1]).is_err());
+        data[0] = 0;
+        assert_eq!(
+            parse_program_config(&data).unwrap_err(),
+            "config account is not initialized"
+        );
+    }
+
+    #[test]
+    fn paused_config_blocks_flows() {
+        assert!(check_not_paused(Some(&config())).is_err());
+        let running = ProgramConfig {
+            paused: false,
+            ..config()
+        };
+        assert!(check_not_paused(Some(&running)).is_ok());
+        assert!(check_not_paused(None).is_ok());
+    }
+
+    #[test]
+    fn update_instruction_encodes_every_field() {
+        let program_id = Pubkey::new_from_array([9; 32]);
+        let ix = build_update_config_instruction(&program_id, &config().admin, &config()).unwrap();
+        let mut expected = vec![IX_UPDATE_CONFIG];
+        expected.extend_from_slice(&account_data(&config())[1..]);
+        assert_eq!(instruction_from_js(&ix).unwrap().data, expected);
+
+        let too_high = ProgramConfig {
+            fee_bps: MAX_FEE_BPS + 1,
+            ..config()
+        };
+        assert!(build_update_config_instruction(&program_id, &config().admin, &too_high).is_err());
+    }
+
+    #[test]
+    fn fee_rounds_down_and_net_covers_the_rest() {
+        let config = config();
+        assert_eq!(
+            config.fee_breakdown(4_200_000),
+            FeeBreakdown {
+                gross: 4_200_000,
+                fee: 10_500,
+                net: 4_189_500,
+            }
+        );
+        assert_eq!(config.fee_for(399), 0);
+        assert_eq!(config.fee_for(u64::MAX), (u64::MAX as u128 * 25 / 10_000) as u64);
+    }
+
+    #[test]
+    fn fee_recipient_only_when_charged() {
+        assert_eq!(config().charged_fee_recipient(), Some(config().fee_recipient));
+        let free = ProgramConfig {
+            fee_bps: 0,
+            ..config()
+        };
+        assert_eq!(free.charged_fee_recipient(), None);
+        assert_eq!(free.fee_breakdown(1_000).net, 1_000);
+    }
+}
@@ -0,0 +1,107 @@
+// Pull-and-swap: a withdraw followed by a swap of the pulled tokens in the
+// same transaction, so the beneficiary settles in a different token than
+// the delegation mint.
+use solana_sdk::pubkey::Pubkey;
+
+use crate::api::execute::realized_output;
+use crate::api::jupiter::JupiterSwapQuote;
+use crate::api::JupiterClient;
+use crate::rpc::{get_transaction, send_transaction_base64, wait_for_confirmation};
+use crate::timed_delegation::{instruction_from_js, JsInstruction};
+use crate::v0_transaction::{build_unsigned, UnsignedTransaction};
+use crate::wallet::WalletAdapter;
+
+/// The withdraw instructions carry a secp256r1 payload, so the swap route is
+/// capped to keep the combined transaction within packet size.
+const SWAP_MAX_ACCOUNTS: u8 = 20;
+/// Compute units for the withdraw and its secp256r1 verification, on top of
+/// the swap's own budget.
+const WITHDRAW_COMPUTE_UNITS: u32 = 100_000;
+const CONFIRM_TIMEOUT_MS: f64 = 60_000.0;
+
+pub struct PullSwapPlan {
+    pub quote: JupiterSwapQuote,
+    pub tx: UnsignedTransaction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PullSwapResult {
+    pub signature: String,
+    pub min_out: u64,
+    pub realized_out: Option<u64>,
+}
+
+/// Appends a swap of `amount` pulled tokens after `withdraw_ixs`. The swap's
+/// minimum output comes from a quote fetched now, not from any earlier
+/// estimate shown to the user.
+pub async fn build_pull_and_swap(
+    rpc_url: &str,
+    beneficiary: &Pubkey,
+    withdraw_ixs: &[JsInstruction],
+    input_mint: &str,
+    output_mint: &str,
+    amount: u64,
+    slippage_bps: u16,
+) -> Result<PullSwapPlan, String> {
+    let quote = JupiterClient::get_swap_quote(
+        input_mint,
+        output_mint,
+        amount,
+        slippage_bps,
+        Some(SWAP_MAX_ACCOUNTS),
+    )
+    .await?;
+    let swap = JupiterClient::get_swap_instructions(&quote, &beneficiary.to_string()).await?;
+
+    // The withdraw pair stays first so the program finds its signature
+    // verification where it expects it; the compute budget goes last.
+    let mut instructions = withdraw_ixs
+        .iter()
+        .map(instruction_from_js)
+        .collect::<Result<Vec<_>, String>>()?;
+    instructions.extend(swap.swap_instructions()?);
+    instructions.extend(swap.compute_budget(WITHDRAW_COMPUTE_UNITS)?);
+
+    let tx = build_unsigned(
+        rpc_url,
+        beneficiary,
+        &instructions,
+        &swap.address_lookup_table_addresses,
+    )
+    .await?;
+    if !tx.fits() {
+        return Err(format!(
+            "withdraw + swap is {} bytes, too large for one transaction",
+            tx.size
+        ));
+    }
+    Ok(PullSwapPlan { quote, tx })
+}
+
+pub async fn execute_pull_and_swap(
+    adapter: &WalletAdapter,
+    rpc_url: &str,
+    beneficiary: &Pubkey,
+    output_mint: &str,
+    plan: &PullSwapPlan,
+) -> Result<PullSwapResult, String> {
+    let signed = adapter.sign_transaction_base64(&plan.tx.base64).await?;
+    let signature = send_transaction_base64(rpc_url, &signed).await?;
+    wait_for_confirmation(rpc_url, &signature, CONFIRM_TIMEOUT_MS).await?;
+    let realized_out = match get_transaction(rpc_url, &signature).await {
+        Ok(info) => realized_output(&info, &beneficiary.to_string(), output_mint),
+        Err(e) => {
+            log::warn!(
+                "Could not load pull-and-swap transaction {}: {}",
+                signature,
+                e
+            );
+            None
+        }
+    };
+    Ok(PullSwapResult {
+        signature,
+        min_out: plan.quote.other_amount_threshold,
+        realized_out,
+    })
+}
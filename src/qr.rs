@@ -0,0 +1,177 @@
+// QR rendering and multi-part framing for payment requests and withdraw vouchers.
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+/// Largest payload rendered as a single QR; anything longer is split so each
+/// code stays dense enough to scan from a phone screen.
+pub const MAX_QR_CHUNK: usize = 600;
+
+const CHUNK_PREFIX: &str = "TDQ:";
+
+/// Most frames a scanned set may announce; far more than any voucher needs,
+/// and it keeps a bogus total from sizing the assembler.
+const MAX_FRAMES: usize = 32;
+
+pub fn render_svg(data: &str) -> Result<String, String> {
+    let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| e.to_string())?;
+    Ok(code
+        .render()
+        .min_dimensions(220, 220)
+        .quiet_zone(true)
+        .dark_color(svg::Color("#0f172a"))
+        .light_color(svg::Color("#ffffff"))
+        .build())
+}
+
+/// Splits `payload` into `TDQ:<index>/<total>:<part>` frames when it does
+/// not fit in one code; short payloads are returned unchanged.
+pub fn split_frames(payload: &str, max_len: usize) -> Vec<String> {
+    if payload.len() <= max_len {
+        return vec![payload.to_string()];
+    }
+    // Cut at the last char boundary within each chunk so non-ASCII text
+    // (a percent-decoded label, say) is never split mid-character.
+    let mut parts: Vec<&str> = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let mut end = max_len.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (part, tail) = rest.split_at(end);
+        parts.push(part);
+        rest = tail;
+    }
+    let total = parts.len();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| format!("{}{}/{}:{}", CHUNK_PREFIX, i + 1, total, part))
+        .collect()
+}
+
+pub fn render_frames_svg(payload: &str) -> Result<Vec<String>, String> {
+    split_frames(payload, MAX_QR_CHUNK)
+        .iter()
+        .map(|frame| render_svg(frame))
+        .collect()
+}
+
+/// Collects scanned frames in any order and yields the payload once complete.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameAssembler {
+    parts: Vec<Option<String>>,
+}
+
+impl FrameAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one scanned string. Returns the full payload when it is a plain
+    /// (unframed) payload or completes the set.
+    pub fn push(&mut self, scanned: &str) -> Result<Option<String>, String> {
+        let scanned = scanned.trim();
+        let Some(rest) = scanned.strip_prefix(CHUNK_PREFIX) else {
+            return Ok(Some(scanned.to_string()));
+        };
+        let (position, part) = rest.split_once(':').ok_or("malformed QR frame")?;
+        let (index, total) = position.split_once('/').ok_or("malformed QR frame")?;
+        let index: usize = index.parse().map_err(|_| "malformed QR frame index")?;
+        let total: usize = total.parse().map_err(|_| "malformed QR frame total")?;
+        if total == 0 || index == 0 || index > total {
+            return Err("QR frame index out of range".to_string());
+        }
+        if total > MAX_FRAMES {
+            return Err(format!("too many QR frames ({} > {})", total, MAX_FRAMES));
+        }
+        if self.parts.len() != total {
+            self.parts = vec![None; total];
+        }
+        self.parts[index - 1] = Some(part.to_string());
+        Ok(self.complete())
+    }
+
+    pub fn progress(&self) -> (usize, usize) {
+        let have = self.parts.iter().filter(|p| p.is_some()).count();
+        (have, self.parts.len())
+    }
+
+    fn complete(&self) -> Option<String> {
+        if self.parts.is_empty() || self.parts.iter().any(|p| p.is_none()) {
+            return None;
+        }
+        Some(self.parts.iter().flatten().map(String::as_str).collect())
+    }
+}
+
+/// Reassembles a payload from pasted text holding one frame per line (or a
+/// single unframed payload).
+pub fn assemble_text(text: &str) -> Result<String, String> {
+    let mut assembler = FrameAssembler::new();
+    let mut payload = None;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        payload = assembler.push(line)?;
+    }
+    payload.ok_or_else(|| {
+        let (have, total) = assembler.progress();
+        format!("missing QR parts ({}/{})", have, total)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_payloads_are_not_framed() {
+        assert_eq!(split_frames("tdv1:abc", 16), vec!["tdv1:abc".to_string()]);
+        assert_eq!(assemble_text("tdv1:abc").unwrap(), "tdv1:abc");
+    }
+
+    #[test]
+    fn frames_reassemble_in_any_order() {
+        let payload = "tdv1:".to_string() + &"x".repeat(25);
+        let mut frames = split_frames(&payload, 10);
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].starts_with("TDQ:1/3:"));
+        frames.reverse();
+        assert_eq!(assemble_text(&frames.join("\n")).unwrap(), payload);
+    }
+
+    #[test]
+    fn splits_on_char_boundaries() {
+        let payload = "caf\u{e9}-\u{1f600}-na\u{ef}ve-\u{e9}t\u{e9}";
+        for max_len in 1..payload.len() {
+            let frames = split_frames(payload, max_len);
+            assert_eq!(assemble_text(&frames.join("\n")).unwrap(), payload, "max_len {}", max_len);
+        }
+    }
+
+    #[test]
+    fn reports_missing_and_bad_frames() {
+        let frames = split_frames(&"y".repeat(30), 10);
+        assert_eq!(
+            assemble_text(&frames[..2].join("\n")).unwrap_err(),
+            "missing QR parts (2/3)"
+        );
+        assert!(assemble_text("TDQ:4/3:abc").is_err());
+        assert!(assemble_text("TDQ:x/3:abc").is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_frame_totals() {
+        let mut assembler = FrameAssembler::default();
+        assert_eq!(
+            assembler.push("TDQ:1/33:abc").unwrap_err(),
+            "too many QR frames (33 > 32)"
+        );
+        assert!(assembler.push(&format!("TDQ:1/{}:abc", usize::MAX)).is_err());
+        assert_eq!(assembler.progress(), (0, 0));
+        assert_eq!(assembler.push("TDQ:1/1:abc").unwrap().as_deref(), Some("abc"));
+    }
+}
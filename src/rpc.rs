@@ -65,3 +65,413 @@ pub async fn get_account_data_base64(rpc_url: &str, pubkey: &str) -> Result<Stri
         .ok_or("account not found")?;
     Ok(value.data.0)
 }
+
+#[derive(Serialize)]
+struct CommitmentConfig {
+    commitment: &'static str,
+}
+
+#[derive(Deserialize)]
+struct LatestBlockhashResult {
+    value: LatestBlockhashValue,
+}
+
+#[derive(Deserialize)]
+struct LatestBlockhashValue {
+    blockhash: String,
+}
+
+pub async fn get_latest_blockhash(rpc_url: &str) -> Result<String, String> {
+    let params = (CommitmentConfig { commitment: "confirmed" },);
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getLatestBlockhash",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<LatestBlockhashResult> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    let result = body.result.ok_or("missing blockhash")?;
+    Ok(result.value.blockhash)
+}
+
+#[derive(Serialize)]
+struct SignaturesConfig {
+    limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignatureInfo {
+    pub signature: String,
+}
+
+#[derive(Serialize)]
+struct TransactionConfig {
+    encoding: &'static str,
+    commitment: &'static str,
+    #[serde(rename = "maxSupportedTransactionVersion")]
+    max_supported_transaction_version: u8,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransactionInfo {
+    pub slot: u64,
+    #[serde(rename = "blockTime")]
+    pub block_time: Option<i64>,
+    pub meta: Option<TransactionMeta>,
+    pub transaction: TransactionBody,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransactionMeta {
+    pub err: Option<serde_json::Value>,
+    pub fee: u64,
+    #[serde(rename = "preBalances", default)]
+    pub pre_balances: Vec<u64>,
+    #[serde(rename = "postBalances", default)]
+    pub post_balances: Vec<u64>,
+    #[serde(rename = "preTokenBalances", default)]
+    pub pre_token_balances: Vec<TokenBalance>,
+    #[serde(rename = "postTokenBalances", default)]
+    pub post_token_balances: Vec<TokenBalance>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenBalance {
+    #[serde(rename = "accountIndex")]
+    pub account_index: usize,
+    pub mint: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(rename = "uiTokenAmount")]
+    pub ui_token_amount: UiTokenAmount,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UiTokenAmount {
+    pub amount: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransactionBody {
+    pub message: TransactionMessage,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransactionMessage {
+    #[serde(rename = "accountKeys")]
+    pub account_keys: Vec<String>,
+    pub instructions: Vec<CompiledInstructionInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompiledInstructionInfo {
+    #[serde(rename = "programIdIndex")]
+    pub program_id_index: usize,
+    pub accounts: Vec<usize>,
+    /// Base58-encoded instruction data.
+    pub data: String,
+}
+
+pub async fn get_signatures_for_address(
+    rpc_url: &str,
+    address: &str,
+    limit: u32,
+    before: Option<&str>,
+) -> Result<Vec<SignatureInfo>, String> {
+    let params = (
+        address,
+        SignaturesConfig {
+            limit,
+            before: before.map(|s| s.to_string()),
+        },
+    );
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getSignaturesForAddress",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<Vec<SignatureInfo>> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    Ok(body.result.unwrap_or_default())
+}
+
+pub async fn get_transaction(rpc_url: &str, signature: &str) -> Result<TransactionInfo, String> {
+    let params = (
+        signature,
+        TransactionConfig {
+            encoding: "json",
+            commitment: "confirmed",
+            max_supported_transaction_version: 0,
+        },
+    );
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getTransaction",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<TransactionInfo> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    body.result.ok_or("transaction not found".to_string())
+}
+
+#[derive(Serialize)]
+struct SendTransactionConfig {
+    encoding: &'static str,
+    #[serde(rename = "preflightCommitment")]
+    preflight_commitment: &'static str,
+}
+
+pub async fn send_transaction_base64(rpc_url: &str, tx_b64: &str) -> Result<String, String> {
+    let params = (
+        tx_b64,
+        SendTransactionConfig {
+            encoding: "base64",
+            preflight_commitment: "confirmed",
+        },
+    );
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "sendTransaction",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<String> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    body.result.ok_or("missing signature".to_string())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignatureStatus {
+    pub err: Option<serde_json::Value>,
+    #[serde(rename = "confirmationStatus")]
+    pub confirmation_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignatureStatusesResult {
+    value: Vec<Option<SignatureStatus>>,
+}
+
+pub async fn get_signature_status(
+    rpc_url: &str,
+    signature: &str,
+) -> Result<Option<SignatureStatus>, String> {
+    let params = ([signature],);
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getSignatureStatuses",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<SignatureStatusesResult> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    Ok(body.result.and_then(|r| r.value.into_iter().next().flatten()))
+}
+
+/// Polls until `signature` is confirmed or `timeout_ms` elapses.
+pub async fn wait_for_confirmation(
+    rpc_url: &str,
+    signature: &str,
+    timeout_ms: f64,
+) -> Result<(), String> {
+    let deadline = js_sys::Date::now() + timeout_ms;
+    loop {
+        if let Some(status) = get_signature_status(rpc_url, signature).await? {
+            if let Some(err) = status.err {
+                return Err(format!("transaction failed: {}", err));
+            }
+            if matches!(
+                status.confirmation_status.as_deref(),
+                Some("confirmed") | Some("finalized")
+            ) {
+                return Ok(());
+            }
+        }
+        if js_sys::Date::now() >= deadline {
+            return Err("timed out waiting for confirmation".to_string());
+        }
+        gloo_timers::future::sleep(std::time::Duration::from_millis(1_000)).await;
+    }
+}
+
+#[derive(Deserialize)]
+struct BalanceResult {
+    value: u64,
+}
+
+pub async fn get_balance(rpc_url: &str, pubkey: &str) -> Result<u64, String> {
+    let params = (pubkey, CommitmentConfig { commitment: "confirmed" });
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getBalance",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<BalanceResult> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    Ok(body.result.ok_or("missing balance")?.value)
+}
+
+#[derive(Serialize)]
+struct ProgramIdFilter<'a> {
+    #[serde(rename = "programId")]
+    program_id: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ParsedTokenAccount {
+    pub mint: String,
+    pub amount: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenAccountsResult {
+    value: Vec<TokenAccountEntry>,
+}
+
+#[derive(Deserialize)]
+struct TokenAccountEntry {
+    account: TokenAccountData,
+}
+
+#[derive(Deserialize)]
+struct TokenAccountData {
+    data: ParsedAccountData,
+}
+
+#[derive(Deserialize)]
+struct ParsedAccountData {
+    parsed: ParsedTokenInfo,
+}
+
+#[derive(Deserialize)]
+struct ParsedTokenInfo {
+    info: TokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+struct TokenAccountInfo {
+    mint: String,
+    #[serde(rename = "tokenAmount")]
+    token_amount: UiTokenAmount,
+}
+
+/// Token accounts held by `owner` under one token program.
+pub async fn get_token_accounts_by_owner(
+    rpc_url: &str,
+    owner: &str,
+    token_program_id: &str,
+) -> Result<Vec<ParsedTokenAccount>, String> {
+    let params = (
+        owner,
+        ProgramIdFilter {
+            program_id: token_program_id,
+        },
+        AccountInfoConfig {
+            encoding: "jsonParsed",
+        },
+    );
+    let req = RpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "getTokenAccountsByOwner",
+        params,
+        _phantom: None,
+    };
+
+    let resp = Request::post(rpc_url)
+        .json(&req)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let body: RpcResponse<TokenAccountsResult> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(err) = body.error {
+        return Err(err.message);
+    }
+    Ok(body
+        .result
+        .ok_or("missing token accounts")?
+        .value
+        .into_iter()
+        .map(|entry| {
+            let info = entry.account.data.parsed.info;
+            ParsedTokenAccount {
+                mint: info.mint,
+                amount: info.token_amount.amount.parse().unwrap_or(0),
+            }
+        })
+        .collect())
+}
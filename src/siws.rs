@@ -0,0 +1,421 @@
+// Sign-In With Solana. The wallet signs a CAIP-122 text message (domain,
+// nonce, issued-at, expiry) instead of a transaction; the signed message is
+// the session credential. Verification is plain Rust with no browser APIs so
+// the keeper and merchant backends can check the same token the dashboard
+// keeps in localStorage. Backends should also remember nonces they accepted
+// to refuse replays within the expiry window.
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
+
+use crate::config;
+use crate::storage::Key;
+use crate::wallet::WalletAdapter;
+
+const SESSION: Key<SiwsSession> = Key::new("td_siws_session", 1);
+const HEADER_SUFFIX: &str = " wants you to sign in with your Solana account:";
+pub const DEFAULT_STATEMENT: &str = "Sign in to manage your timed delegations.";
+pub const SESSION_TTL_SECS: i64 = 24 * 60 * 60;
+/// Tolerated clock drift between the wallet's device and the verifier.
+const CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiwsMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: String,
+    pub nonce: String,
+    pub issued_at: i64,
+    pub expiration_time: i64,
+}
+
+impl SiwsMessage {
+    pub fn new(domain: &str, uri: &str, address: &str, now: i64, ttl_secs: i64) -> Result<Self, String> {
+        Ok(Self {
+            domain: domain.to_string(),
+            address: address.to_string(),
+            statement: Some(DEFAULT_STATEMENT.to_string()),
+            uri: uri.to_string(),
+            version: "1".to_string(),
+            chain_id: config::cluster().as_str().to_string(),
+            nonce: new_nonce()?,
+            issued_at: now,
+            expiration_time: now + ttl_secs,
+        })
+    }
+
+    /// The exact text the wallet shows and signs.
+    pub fn to_message(&self) -> String {
+        let mut text = format!("{}{}\n{}", self.domain, HEADER_SUFFIX, self.address);
+        if let Some(statement) = &self.statement {
+            text.push_str(&format!("\n\n{}", statement));
+        }
+        text.push_str(&format!(
+            "\n\nURI: {}\nVersion: {}\nChain ID: {}\nNonce: {}\nIssued At: {}\nExpiration Time: {}",
+            self.uri,
+            self.version,
+            self.chain_id,
+            self.nonce,
+            format_timestamp(self.issued_at),
+            format_timestamp(self.expiration_time),
+        ));
+        text
+    }
+
+    /// Inverse of [`to_message`](Self::to_message). The statement may span
+    /// several lines; only the fields this module writes are accepted after it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (header, rest) = text.split_once('\n').ok_or("not a sign-in message")?;
+        let domain = header
+            .strip_suffix(HEADER_SUFFIX)
+            .ok_or("not a sign-in message")?
+            .to_string();
+        // Fields start at the last blank-line-then-URI, so a statement that
+        // mentions "URI: " cannot be mistaken for them.
+        let (head, tail) = rest.rsplit_once("\n\nURI: ").ok_or("missing URI")?;
+        let (address, statement) = match head.split_once("\n\n") {
+            Some((address, statement)) => (address, Some(statement.to_string())),
+            None => (head, None),
+        };
+        let address = address.trim().to_string();
+        if address.is_empty() || address.contains('\n') {
+            return Err("missing address".to_string());
+        }
+
+        let fields_text = format!("URI: {}", tail);
+        let mut fields: Vec<(&str, &str)> = Vec::new();
+        for line in fields_text.lines() {
+            match line.split_once(": ") {
+                Some((key, value)) if FIELDS.contains(&key) => fields.push((key, value)),
+                _ => return Err(format!("unexpected line: {}", line)),
+            }
+        }
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+                .ok_or_else(|| format!("missing {}", key))
+        };
+        Ok(Self {
+            domain,
+            address,
+            statement,
+            uri: field("URI")?,
+            version: field("Version")?,
+            chain_id: field("Chain ID")?,
+            nonce: field("Nonce")?,
+            issued_at: parse_timestamp(&field("Issued At")?)?,
+            expiration_time: parse_timestamp(&field("Expiration Time")?)?,
+        })
+    }
+}
+
+const FIELDS: [&str; 6] = ["URI", "Version", "Chain ID", "Nonce", "Issued At", "Expiration Time"];
+
+fn new_nonce() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bs58::encode(bytes).into_string())
+}
+
+/// Checks the signature, domain and validity window of a signed message
+/// and returns it parsed. `now` is unix seconds.
+pub fn verify(message: &str, signature: &[u8], expected_domain: &str, now: i64) -> Result<SiwsMessage, String> {
+    let parsed = SiwsMessage::parse(message)?;
+    if parsed.domain != expected_domain {
+        return Err(format!("message is for {}, not {}", parsed.domain, expected_domain));
+    }
+    if parsed.issued_at > now + CLOCK_SKEW_SECS {
+        return Err("message is issued in the future".to_string());
+    }
+    if parsed.expiration_time <= now {
+        return Err("sign-in has expired".to_string());
+    }
+    let pubkey = Pubkey::from_str(&parsed.address).map_err(|_| "invalid address in message")?;
+    let signature = Signature::try_from(signature).map_err(|_| "signature must be 64 bytes")?;
+    if !signature.verify(pubkey.as_ref(), message.as_bytes()) {
+        return Err("signature does not match address".to_string());
+    }
+    Ok(parsed)
+}
+
+/// A verified sign-in. `message` and `signature` (base64) are all a
+/// backend needs; see [`SiwsSession::token`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SiwsSession {
+    pub address: String,
+    pub message: String,
+    pub signature: String,
+    pub expires_at: i64,
+}
+
+impl SiwsSession {
+    pub fn is_valid_for(&self, address: &str, now: i64) -> bool {
+        self.address == address && self.expires_at > now
+    }
+
+    /// Opaque bearer token: base64 of the session JSON.
+    pub fn token(&self) -> String {
+        B64.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn authorization_header(&self) -> String {
+        format!("SIWS {}", self.token())
+    }
+}
+
+/// Backend side of [`SiwsSession::authorization_header`]: accepts the
+/// header value or the bare token and returns the signed-in message.
+pub fn verify_token(token: &str, expected_domain: &str, now: i64) -> Result<SiwsMessage, String> {
+    let token = token.trim();
+    let token = token.strip_prefix("SIWS ").unwrap_or(token);
+    let raw = B64.decode(token).map_err(|_| "token is not base64")?;
+    let session: SiwsSession = serde_json::from_slice(&raw).map_err(|_| "malformed token")?;
+    let signature = B64.decode(&session.signature).map_err(|_| "signature is not base64")?;
+    let message = verify(&session.message, &signature, expected_domain, now)?;
+    if message.address != session.address {
+        return Err("token address does not match the signed message".to_string());
+    }
+    Ok(message)
+}
+
+/// Asks the wallet to sign a fresh message for this page, verifies the
+/// signature locally and stores the session.
+pub async fn sign_in(adapter: &WalletAdapter, address: &str) -> Result<SiwsSession, String> {
+    let location = web_sys::window().ok_or("window not available")?.location();
+    let domain = location.host().map_err(|_| "page host unavailable")?;
+    let uri = location.origin().map_err(|_| "page origin unavailable")?;
+    let now = now_secs();
+
+    let message = SiwsMessage::new(&domain, &uri, address, now, SESSION_TTL_SECS)?.to_message();
+    let signature_b64 = adapter.sign_message_base64(&B64.encode(&message)).await?;
+    let signature = B64.decode(&signature_b64).map_err(|e| e.to_string())?;
+    let verified = verify(&message, &signature, &domain, now)?;
+
+    let session = SiwsSession {
+        address: verified.address,
+        message,
+        signature: signature_b64,
+        expires_at: verified.expiration_time,
+    };
+    save_session(&session);
+    Ok(session)
+}
+
+pub fn now_secs() -> i64 {
+    (js_sys::Date::now() / 1000.0) as i64
+}
+
+/// The stored session, if it still verifies for this page the way a
+/// backend would check it. Expired or edited sessions are ignored.
+pub fn load_session() -> Option<SiwsSession> {
+    let domain = web_sys::window()?.location().host().ok()?;
+    let session = SESSION.load()?;
+    match verify_token(&session.authorization_header(), &domain, now_secs()) {
+        Ok(_) => Some(session),
+        Err(e) => {
+            log::info!("Ignoring stored sign-in: {}", e);
+            None
+        }
+    }
+}
+
+fn save_session(session: &SiwsSession) {
+    SESSION.save(session);
+}
+
+pub fn clear_session() {
+    SESSION.clear();
+}
+
+/// RFC 3339 in UTC, e.g. `2024-05-01T12:00:00Z`.
+pub fn format_timestamp(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Accepts UTC RFC 3339 timestamps, with or without fractional seconds.
+pub fn parse_timestamp(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid timestamp: {}", value);
+    let value = value.trim().strip_suffix('Z').ok_or_else(invalid)?;
+    let (date, time) = value.split_once('T').ok_or_else(invalid)?;
+    let time = time.split('.').next().unwrap_or(time);
+
+    let number = |s: &str| s.parse::<i64>().map_err(|_| invalid());
+    let date: Vec<&str> = date.split('-').collect();
+    let time: Vec<&str> = time.split(':').collect();
+    if date.len() != 3 || time.len() != 3 {
+        return Err(invalid());
+    }
+    let (year, month, day) = (number(date[0])?, number(date[1])?, number(date[2])?);
+    let (hour, minute, second) = (number(time[0])?, number(time[1])?, number(time[2])?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+// Proleptic Gregorian conversions (H. Hinnant's algorithms).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    const DOMAIN: &str = "app.example.com";
+    const NOW: i64 = 1_714_564_800; // 2024-05-01T12:00:00Z
+
+    fn message(keypair: &Keypair) -> SiwsMessage {
+        SiwsMessage {
+            domain: DOMAIN.to_string(),
+            address: keypair.pubkey().to_string(),
+            statement: Some(DEFAULT_STATEMENT.to_string()),
+            uri: format!("https://{}", DOMAIN),
+            version: "1".to_string(),
+            chain_id: "mainnet".to_string(),
+            nonce: "3n8xQJ7yJd9c".to_string(),
+            issued_at: NOW,
+            expiration_time: NOW + SESSION_TTL_SECS,
+        }
+    }
+
+    fn signed(keypair: &Keypair, message: &SiwsMessage) -> (String, Vec<u8>) {
+        let text = message.to_message();
+        let signature = keypair.sign_message(text.as_bytes());
+        (text, signature.as_ref().to_vec())
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        assert_eq!(format_timestamp(NOW), "2024-05-01T12:00:00Z");
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00.250Z").unwrap(), NOW);
+        assert_eq!(parse_timestamp(&format_timestamp(-86_399)).unwrap(), -86_399);
+        assert!(parse_timestamp("2024-13-01T00:00:00Z").is_err());
+        assert!(parse_timestamp("2024-05-01 12:00:00").is_err());
+    }
+
+    #[test]
+    fn message_round_trips() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let mut msg = message(&keypair);
+        assert_eq!(SiwsMessage::parse(&msg.to_message()).unwrap(), msg);
+        msg.statement = None;
+        assert_eq!(SiwsMessage::parse(&msg.to_message()).unwrap(), msg);
+    }
+
+    #[test]
+    fn multi_line_statements_round_trip() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let mut msg = message(&keypair);
+        msg.statement = Some("First line.\nSecond line.\n\nURI: is just text here.".to_string());
+        assert_eq!(SiwsMessage::parse(&msg.to_message()).unwrap(), msg);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let text = format!("{}\nRequest ID: 1", message(&keypair).to_message());
+        assert!(SiwsMessage::parse(&text).unwrap_err().starts_with("unexpected line"));
+    }
+
+    #[test]
+    fn verifies_a_signed_message() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let msg = message(&keypair);
+        let (text, signature) = signed(&keypair, &msg);
+        assert_eq!(verify(&text, &signature, DOMAIN, NOW + 10).unwrap(), msg);
+    }
+
+    #[test]
+    fn rejects_wrong_domain() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let (text, signature) = signed(&keypair, &message(&keypair));
+        assert!(verify(&text, &signature, "evil.example.com", NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_expired_and_future_messages() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let (text, signature) = signed(&keypair, &message(&keypair));
+        assert_eq!(
+            verify(&text, &signature, DOMAIN, NOW + SESSION_TTL_SECS).unwrap_err(),
+            "sign-in has expired"
+        );
+        assert_eq!(
+            verify(&text, &signature, DOMAIN, NOW - CLOCK_SKEW_SECS - 1).unwrap_err(),
+            "message is issued in the future"
+        );
+        assert!(verify(&text, &signature, DOMAIN, NOW - CLOCK_SKEW_SECS).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_signature_or_message() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let (text, mut signature) = signed(&keypair, &message(&keypair));
+        let tampered_text = text.replace("3n8xQJ7yJd9c", "3n8xQJ7yJd9d");
+        assert!(verify(&tampered_text, &signature, DOMAIN, NOW).is_err());
+        signature[0] ^= 1;
+        assert_eq!(
+            verify(&text, &signature, DOMAIN, NOW).unwrap_err(),
+            "signature does not match address"
+        );
+    }
+
+    #[test]
+    fn token_round_trips_and_checks_address() {
+        let keypair = Keypair::new_from_array([7; 32]);
+        let msg = message(&keypair);
+        let (text, signature) = signed(&keypair, &msg);
+        let mut session = SiwsSession {
+            address: msg.address.clone(),
+            message: text,
+            signature: B64.encode(&signature),
+            expires_at: msg.expiration_time,
+        };
+        assert_eq!(verify_token(&session.authorization_header(), DOMAIN, NOW).unwrap(), msg);
+        assert!(session.is_valid_for(&msg.address, NOW));
+
+        session.address = Keypair::new_from_array([8; 32]).pubkey().to_string();
+        assert_eq!(
+            verify_token(&session.token(), DOMAIN, NOW).unwrap_err(),
+            "token address does not match the signed message"
+        );
+        assert!(!session.is_valid_for(&msg.address, NOW));
+    }
+}
@@ -0,0 +1,428 @@
+// Solana Pay transaction-request endpoint for delegation setup.
+//
+// A wallet scanning `solana:<endpoint>?pr=1&beneficiary=...` sends GET for the
+// merchant label/icon, then POSTs `{"account": "<delegator>"}` and receives the
+// create-delegation (+ optional set-auth) transaction to sign. The handler is
+// transport-agnostic so it can sit behind any HTTP server or a local mock.
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::Hash, message::Message, pubkey::Pubkey};
+use std::future::Future;
+use std::str::FromStr;
+
+use crate::payment_request::{percent_encode, query_param, PaymentRequest};
+use crate::rpc::get_latest_blockhash;
+use crate::timed_delegation::{
+    build_create_delegation_instructions, build_memo_instruction, build_set_auth_instructions,
+    decode_base64, encode_message_base64, instruction_from_js, parse_amount, SpendingRules,
+};
+
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequestMeta {
+    pub label: String,
+    pub icon: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionRequestBody {
+    pub account: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequestResponse {
+    pub transaction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Source of the recent blockhash embedded in served transactions.
+pub trait BlockhashSource {
+    fn latest_blockhash(&self) -> impl Future<Output = Result<Hash, String>>;
+}
+
+pub struct RpcBlockhashSource {
+    pub rpc_url: String,
+}
+
+impl BlockhashSource for RpcBlockhashSource {
+    async fn latest_blockhash(&self) -> Result<Hash, String> {
+        let blockhash = get_latest_blockhash(&self.rpc_url).await?;
+        Hash::from_str(&blockhash).map_err(|e| e.to_string())
+    }
+}
+
+pub struct TransactionRequestHandler<B> {
+    program_id: Pubkey,
+    mint: Pubkey,
+    decimals: u8,
+    meta: TransactionRequestMeta,
+    blockhash: B,
+}
+
+impl<B: BlockhashSource> TransactionRequestHandler<B> {
+    pub fn new(
+        program_id: Pubkey,
+        mint: Pubkey,
+        decimals: u8,
+        label: &str,
+        icon: &str,
+        blockhash: B,
+    ) -> Self {
+        Self {
+            program_id,
+            mint,
+            decimals,
+            meta: TransactionRequestMeta {
+                label: label.to_string(),
+                icon: icon.to_string(),
+            },
+            blockhash,
+        }
+    }
+
+    /// Dispatches a Solana Pay transaction request. `now` is the unix time
+    /// used as the delegation start.
+    pub async fn handle(&self, req: &HttpRequest, now: i64) -> HttpResponse {
+        match req.method.as_str() {
+            "GET" => json_response(200, &self.meta),
+            "POST" => {
+                let account = req
+                    .body
+                    .as_deref()
+                    .ok_or("missing request body".to_string())
+                    .and_then(|b| {
+                        serde_json::from_str::<TransactionRequestBody>(b).map_err(|e| e.to_string())
+                    });
+                let result = match account {
+                    Ok(body) => self.build_transaction(&req.url, &body.account, now).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(resp) => json_response(200, &resp),
+                    Err(e) => json_response(400, &serde_json::json!({ "message": e })),
+                }
+            }
+            "OPTIONS" => HttpResponse {
+                status: 204,
+                headers: cors_headers(),
+                body: String::new(),
+            },
+            _ => json_response(405, &serde_json::json!({ "message": "method not allowed" })),
+        }
+    }
+
+    pub async fn build_transaction(
+        &self,
+        url: &str,
+        account: &str,
+        now: i64,
+    ) -> Result<TransactionRequestResponse, String> {
+        let request = PaymentRequest::parse(url)?.ok_or("not a payment request")?;
+        if let Some(mint) = &request.mint {
+            if mint.trim() != self.mint.to_string() {
+                return Err(format!("unsupported mint {}", mint));
+            }
+        }
+        let delegator = Pubkey::from_str(account.trim()).map_err(|_| "invalid account")?;
+        let beneficiary =
+            Pubkey::from_str(request.beneficiary.trim()).map_err(|_| "invalid beneficiary")?;
+        let amount = request.amount.as_deref().ok_or("amount is required")?;
+        let max_amount = parse_amount(amount, self.decimals)?;
+        let duration_hours = request.duration_hours.unwrap_or(1);
+        let start_ts = now - 60;
+        let end_ts = duration_hours
+            .checked_mul(3600)
+            .and_then(|secs| now.checked_add(secs))
+            .ok_or("duration too large")?;
+
+        let (_, mut instructions) = build_create_delegation_instructions(
+            &self.program_id,
+            &delegator,
+            &beneficiary,
+            &self.mint,
+            max_amount,
+            start_ts,
+            end_ts,
+            &SpendingRules::default(),
+        )?;
+        if let Some(auth_b64) = query_param(url, "auth") {
+            let auth_pubkey = decode_base64(&auth_b64)?;
+            let (_, auth_ixs) = build_set_auth_instructions(
+                &self.program_id,
+                &delegator,
+                &beneficiary,
+                &self.mint,
+                &auth_pubkey,
+            )?;
+            instructions.extend(auth_ixs);
+        }
+        if let Some(memo) = &request.memo {
+            instructions.push(build_memo_instruction(&delegator, memo));
+        }
+
+        let instructions = instructions
+            .iter()
+            .map(instruction_from_js)
+            .collect::<Result<Vec<_>, _>>()?;
+        let blockhash = self.blockhash.latest_blockhash().await?;
+        let message = Message::new_with_blockhash(&instructions, Some(&delegator), &blockhash);
+        let transaction = serialize_unsigned_transaction(&message);
+
+        let label = request.label.as_deref().unwrap_or(&self.meta.label);
+        Ok(TransactionRequestResponse {
+            transaction: encode_message_base64(&transaction),
+            message: Some(format!("Allow {} to pull up to {} over {}h", label, amount, duration_hours)),
+        })
+    }
+}
+
+/// The endpoint URL a wallet fetches for this request.
+pub fn transaction_request_url(
+    endpoint: &str,
+    request: &PaymentRequest,
+    auth_pubkey_b64: Option<&str>,
+) -> String {
+    let mut url = request.to_url(endpoint);
+    if let Some(auth) = auth_pubkey_b64 {
+        url.push_str("&auth=");
+        url.push_str(&percent_encode(auth));
+    }
+    url
+}
+
+/// Builds the `solana:` link a wallet scans to reach `endpoint` with this request.
+pub fn transaction_request_link(
+    endpoint: &str,
+    request: &PaymentRequest,
+    auth_pubkey_b64: Option<&str>,
+) -> String {
+    format!(
+        "solana:{}",
+        percent_encode(&transaction_request_url(endpoint, request, auth_pubkey_b64))
+    )
+}
+
+/// Wire format with zeroed signature slots for the wallet to fill in.
+fn serialize_unsigned_transaction(message: &Message) -> Vec<u8> {
+    let num_signatures = message.header.num_required_signatures as u16;
+    let mut out = Vec::new();
+    push_short_u16(&mut out, num_signatures);
+    out.resize(out.len() + 64 * num_signatures as usize, 0);
+    out.extend_from_slice(&message.serialize());
+    out
+}
+
+fn push_short_u16(out: &mut Vec<u8>, mut value: u16) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        byte |= 0x80;
+        out.push(byte);
+    }
+}
+
+fn cors_headers() -> Vec<(String, String)> {
+    vec![
+        ("Access-Control-Allow-Origin".to_string(), "*".to_string()),
+        ("Access-Control-Allow-Methods".to_string(), "GET, POST, OPTIONS".to_string()),
+        ("Access-Control-Allow-Headers".to_string(), "Content-Type".to_string()),
+    ]
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> HttpResponse {
+    let mut headers = cors_headers();
+    headers.push(("Content-Type".to_string(), "application/json".to_string()));
+    HttpResponse {
+        status,
+        headers,
+        body: serde_json::to_string(body).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timed_delegation::{DEFAULT_PROGRAM_ID, USDC_MINT};
+    use futures::executor::block_on;
+    use solana_sdk::transaction::Transaction;
+
+    const ENDPOINT: &str = "https://pay.example/api/delegate";
+    const BENEFICIARY: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+    const NOW: i64 = 1_700_000_000;
+
+    struct FixedBlockhashSource(Hash);
+
+    impl BlockhashSource for FixedBlockhashSource {
+        async fn latest_blockhash(&self) -> Result<Hash, String> {
+            Ok(self.0)
+        }
+    }
+
+    fn handler() -> TransactionRequestHandler<FixedBlockhashSource> {
+        TransactionRequestHandler::new(
+            Pubkey::from_str(DEFAULT_PROGRAM_ID).unwrap(),
+            Pubkey::from_str(USDC_MINT).unwrap(),
+            6,
+            "Acme",
+            "https://pay.example/icon.png",
+            FixedBlockhashSource(Hash::new_from_array([7; 32])),
+        )
+    }
+
+    fn request_url(auth: Option<&str>) -> String {
+        let request = PaymentRequest {
+            amount: Some("10".to_string()),
+            duration_hours: Some(24),
+            ..PaymentRequest::new(BENEFICIARY)
+        };
+        transaction_request_url(ENDPOINT, &request, auth)
+    }
+
+    fn post(url: &str, body: Option<&str>) -> HttpResponse {
+        let req = HttpRequest {
+            method: "POST".to_string(),
+            url: url.to_string(),
+            body: body.map(str::to_string),
+        };
+        block_on(handler().handle(&req, NOW))
+    }
+
+    fn has_cors(resp: &HttpResponse) -> bool {
+        resp.headers
+            .iter()
+            .any(|(k, v)| k == "Access-Control-Allow-Origin" && v == "*")
+    }
+
+    fn error_message(resp: &HttpResponse) -> String {
+        let body: serde_json::Value = serde_json::from_str(&resp.body).unwrap();
+        body["message"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn get_returns_label_and_icon() {
+        let req = HttpRequest {
+            method: "GET".to_string(),
+            url: request_url(None),
+            body: None,
+        };
+        let resp = block_on(handler().handle(&req, NOW));
+        assert_eq!(resp.status, 200);
+        assert!(has_cors(&resp));
+        let meta: TransactionRequestMeta = serde_json::from_str(&resp.body).unwrap();
+        assert_eq!(meta.label, "Acme");
+        assert_eq!(meta.icon, "https://pay.example/icon.png");
+    }
+
+    #[test]
+    fn options_is_an_empty_preflight() {
+        let req = HttpRequest {
+            method: "OPTIONS".to_string(),
+            url: ENDPOINT.to_string(),
+            body: None,
+        };
+        let resp = block_on(handler().handle(&req, NOW));
+        assert_eq!(resp.status, 204);
+        assert!(resp.body.is_empty());
+        assert!(has_cors(&resp));
+    }
+
+    #[test]
+    fn post_builds_unsigned_transaction_for_account() {
+        let account = Pubkey::new_unique();
+        let body = format!(r#"{{"account":"{}"}}"#, account);
+        let resp = post(&request_url(None), Some(&body));
+        assert_eq!(resp.status, 200, "{}", resp.body);
+
+        let parsed: TransactionRequestResponse = serde_json::from_str(&resp.body).unwrap();
+        assert_eq!(parsed.message.as_deref(), Some("Allow Acme to pull up to 10 over 24h"));
+        let bytes = decode_base64(&parsed.transaction).unwrap();
+        let tx: Transaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(tx.signatures.len(), 1);
+        assert_eq!(tx.signatures[0], Default::default());
+        assert_eq!(tx.message.account_keys[0], account);
+        assert_eq!(tx.message.recent_blockhash, Hash::new_from_array([7; 32]));
+    }
+
+    #[test]
+    fn post_with_auth_adds_set_auth_instructions() {
+        let account = Pubkey::new_unique();
+        let body = format!(r#"{{"account":"{}"}}"#, account);
+        let instruction_count = |url: &str| {
+            let parsed: TransactionRequestResponse =
+                serde_json::from_str(&post(url, Some(&body)).body).unwrap();
+            let tx: Transaction =
+                bincode::deserialize(&decode_base64(&parsed.transaction).unwrap()).unwrap();
+            tx.message.instructions.len()
+        };
+        let auth = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [2u8; 33]);
+        assert!(instruction_count(&request_url(Some(&auth))) > instruction_count(&request_url(None)));
+    }
+
+    #[test]
+    fn post_rejects_invalid_account() {
+        let resp = post(&request_url(None), Some(r#"{"account":"not-a-key"}"#));
+        assert_eq!(resp.status, 400);
+        assert_eq!(error_message(&resp), "invalid account");
+    }
+
+    #[test]
+    fn post_rejects_missing_body_and_foreign_mint() {
+        assert_eq!(post(&request_url(None), None).status, 400);
+
+        let request = PaymentRequest {
+            mint: Some(BENEFICIARY.to_string()),
+            amount: Some("1".to_string()),
+            ..PaymentRequest::new(BENEFICIARY)
+        };
+        let body = format!(r#"{{"account":"{}"}}"#, Pubkey::new_unique());
+        let resp = post(&request.to_url(ENDPOINT), Some(&body));
+        assert_eq!(resp.status, 400);
+        assert!(error_message(&resp).starts_with("unsupported mint"));
+    }
+
+    #[test]
+    fn post_rejects_overflowing_duration() {
+        let request = PaymentRequest {
+            amount: Some("1".to_string()),
+            duration_hours: Some(i64::MAX / 1000),
+            ..PaymentRequest::new(BENEFICIARY)
+        };
+        let body = format!(r#"{{"account":"{}"}}"#, Pubkey::new_unique());
+        let resp = post(&transaction_request_url(ENDPOINT, &request, None), Some(&body));
+        assert_eq!(resp.status, 400);
+        assert_eq!(error_message(&resp), "duration too large");
+    }
+
+    #[test]
+    fn other_methods_are_not_allowed() {
+        let req = HttpRequest {
+            method: "PUT".to_string(),
+            url: ENDPOINT.to_string(),
+            body: None,
+        };
+        assert_eq!(block_on(handler().handle(&req, NOW)).status, 405);
+    }
+
+    #[test]
+    fn link_wraps_percent_encoded_endpoint() {
+        let link = transaction_request_link(ENDPOINT, &PaymentRequest::new(BENEFICIARY), None);
+        assert!(link.starts_with("solana:https%3A%2F%2Fpay.example%2Fapi%2Fdelegate%3Fpr%3D1"));
+    }
+}
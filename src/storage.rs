@@ -0,0 +1,242 @@
+// Typed, versioned localStorage. Every value is stored as `{"v": n, "data":
+// ...}` under a `td_` key; a `Key` names its type and schema version, and a
+// stored value with any other version is dropped rather than misread. Bump a
+// key's version whenever its type changes shape. Values written before
+// versioning (plain JSON, or a bare string for simple settings) are read as
+// version 1.
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+use crate::config::Cluster;
+
+const KEY_PREFIX: &str = "td_";
+/// Written by the passkey helper in index.html; clearing it would strand
+/// the delegation's authenticator, so "clear data" leaves it alone.
+const PRESERVED_KEYS: [&str; 1] = ["td_passkey"];
+const LEGACY_VERSION: u32 = 1;
+const MAX_RECENT_BENEFICIARIES: usize = 8;
+const MAX_PENDING_TXS: usize = 10;
+
+pub struct Key<T> {
+    name: &'static str,
+    version: u32,
+    _value: PhantomData<fn() -> T>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<V> {
+    v: u32,
+    data: V,
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+impl<T: Serialize + DeserializeOwned> Key<T> {
+    pub const fn new(name: &'static str, version: u32) -> Self {
+        Self {
+            name,
+            version,
+            _value: PhantomData,
+        }
+    }
+
+    pub fn load(&self) -> Option<T> {
+        let raw = storage()?.get_item(self.name).ok()??;
+        match self.decode(raw) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::info!("Dropping {}: {}", self.name, e);
+                self.clear();
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, value: &T) {
+        if let (Some(storage), Ok(raw)) = (storage(), self.encode(value)) {
+            let _ = storage.set_item(self.name, &raw);
+        }
+    }
+
+    fn encode(&self, value: &T) -> Result<String, String> {
+        let envelope = Envelope {
+            v: self.version,
+            data: value,
+        };
+        serde_json::to_string(&envelope).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, raw: String) -> Result<T, String> {
+        let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+        let (version, data) = match value {
+            Value::Object(mut map) if map.len() == 2 && map.contains_key("v") => (
+                map.get("v").and_then(Value::as_u64).unwrap_or(0) as u32,
+                map.remove("data").unwrap_or(Value::Null),
+            ),
+            legacy => (LEGACY_VERSION, legacy),
+        };
+        if version != self.version {
+            return Err(format!("schema v{}, expected v{}", version, self.version));
+        }
+        serde_json::from_value(data).map_err(|e| format!("unreadable: {}", e))
+    }
+
+    pub fn clear(&self) {
+        if let Some(storage) = storage() {
+            let _ = storage.remove_item(self.name);
+        }
+    }
+}
+
+/// Removes everything the app has saved on this device except the passkey
+/// record.
+pub fn clear_all() {
+    let Some(storage) = storage() else {
+        return;
+    };
+    let len = storage.length().unwrap_or(0);
+    let keys: Vec<String> = (0..len)
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|k| k.starts_with(KEY_PREFIX) && !PRESERVED_KEYS.contains(&k.as_str()))
+        .collect();
+    for key in keys {
+        let _ = storage.remove_item(&key);
+    }
+}
+
+/// Unsent form input in the delegation modal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DelegationDraft {
+    pub mode: String,
+    pub delegate_beneficiary: String,
+    pub delegate_amount: String,
+    pub delegate_duration_hours: String,
+    pub delegate_max_per_withdraw: String,
+    pub delegate_cooldown_minutes: String,
+    pub delegate_daily_cap: String,
+    pub withdraw_beneficiary: String,
+    pub withdraw_delegator: String,
+    pub withdraw_amount: String,
+    pub auth_expiry_minutes: String,
+}
+
+impl Default for DelegationDraft {
+    fn default() -> Self {
+        Self {
+            mode: "delegate".to_string(),
+            delegate_beneficiary: String::new(),
+            delegate_amount: "10".to_string(),
+            delegate_duration_hours: "1".to_string(),
+            delegate_max_per_withdraw: String::new(),
+            delegate_cooldown_minutes: String::new(),
+            delegate_daily_cap: String::new(),
+            withdraw_beneficiary: String::new(),
+            withdraw_delegator: String::new(),
+            withdraw_amount: "4.2".to_string(),
+            auth_expiry_minutes: "2".to_string(),
+        }
+    }
+}
+
+/// A transaction sent to the cluster whose confirmation was not seen yet,
+/// e.g. because the page was reloaded while waiting.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingTx {
+    pub signature: String,
+    pub cluster: Cluster,
+    pub sent_ms: f64,
+}
+
+pub const DRAFT: Key<DelegationDraft> = Key::new("td_draft", 1);
+/// `WalletChoice::name` of the last wallet the user connected.
+pub const LAST_WALLET: Key<String> = Key::new("td_last_wallet", 1);
+pub const RECENT_BENEFICIARIES: Key<Vec<String>> = Key::new("td_recent_beneficiaries", 1);
+pub const PENDING_TXS: Key<Vec<PendingTx>> = Key::new("td_pending_txs", 1);
+
+pub fn recent_beneficiaries() -> Vec<String> {
+    RECENT_BENEFICIARIES.load().unwrap_or_default()
+}
+
+/// Moves `address` to the front of the recent list.
+pub fn remember_beneficiary(address: &str) {
+    let mut recent = recent_beneficiaries();
+    recent.retain(|a| a != address);
+    recent.insert(0, address.to_string());
+    recent.truncate(MAX_RECENT_BENEFICIARIES);
+    RECENT_BENEFICIARIES.save(&recent);
+}
+
+pub fn pending_txs(cluster: Cluster) -> Vec<PendingTx> {
+    PENDING_TXS
+        .load()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|tx| tx.cluster == cluster)
+        .collect()
+}
+
+pub fn add_pending_tx(signature: &str, cluster: Cluster) {
+    let mut pending = PENDING_TXS.load().unwrap_or_default();
+    pending.retain(|tx| tx.signature != signature);
+    pending.insert(
+        0,
+        PendingTx {
+            signature: signature.to_string(),
+            cluster,
+            sent_ms: js_sys::Date::now(),
+        },
+    );
+    pending.truncate(MAX_PENDING_TXS);
+    PENDING_TXS.save(&pending);
+}
+
+pub fn remove_pending_tx(signature: &str) {
+    let mut pending = PENDING_TXS.load().unwrap_or_default();
+    pending.retain(|tx| tx.signature != signature);
+    PENDING_TXS.save(&pending);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAFT_V2: Key<DelegationDraft> = Key::new("td_draft", 2);
+
+    #[test]
+    fn envelope_round_trips() {
+        let draft = DelegationDraft {
+            delegate_beneficiary: "Bene111111111111111111111111111111111111111".to_string(),
+            ..Default::default()
+        };
+        let raw = DRAFT.encode(&draft).unwrap();
+        assert!(raw.starts_with(r#"{"v":1,"data":{"#));
+        assert_eq!(DRAFT.decode(raw).unwrap(), draft);
+    }
+
+    #[test]
+    fn other_versions_are_dropped() {
+        let raw = DRAFT.encode(&DelegationDraft::default()).unwrap();
+        assert_eq!(DRAFT_V2.decode(raw).unwrap_err(), "schema v1, expected v2");
+    }
+
+    #[test]
+    fn legacy_values_read_as_version_one() {
+        assert_eq!(LAST_WALLET.decode("Phantom".to_string()).unwrap(), "Phantom");
+        assert_eq!(
+            RECENT_BENEFICIARIES.decode(r#"["a","b"]"#.to_string()).unwrap(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert!(DRAFT_V2.decode(r#"{"mode":"delegate"}"#.to_string()).is_err());
+    }
+
+    #[test]
+    fn unreadable_data_is_an_error() {
+        assert!(RECENT_BENEFICIARIES
+            .decode(r#"{"v":1,"data":"not a list"}"#.to_string())
+            .unwrap_err()
+            .starts_with("unreadable"));
+    }
+}
@@ -0,0 +1,317 @@
+// Funds a USDC delegation from another token: swap first, then create the
+// delegation for what the swap delivered. Both steps go into one
+// transaction when it fits; otherwise the swap lands on its own and the
+// delegation step is recorded so it can be resumed if it fails.
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::api::execute::execute_quote;
+use crate::api::quote::{fetch_with_timeout, SwapMode};
+use crate::api::ranking::rank_quotes;
+use crate::api::{default_providers, JupiterClient, QuoteRequest};
+use crate::program_config::ensure_not_paused;
+use crate::rpc::{get_account_data_base64, send_transaction_base64, wait_for_confirmation};
+use crate::timed_delegation::{
+    build_create_delegation_instructions, build_set_auth_instructions, derive_addresses,
+    instruction_from_js, JsInstruction, SpendingRules,
+};
+use crate::storage::Key;
+use crate::v0_transaction::{build_unsigned, UnsignedTransaction};
+use crate::wallet::WalletAdapter;
+
+const PENDING: Key<PendingSwapDelegation> = Key::new("td_pending_swap_delegation", 1);
+const CONFIRM_TIMEOUT_MS: f64 = 60_000.0;
+/// Compute units for creating the delegation and setting its passkey, on top
+/// of the swap's own budget.
+const DELEGATION_COMPUTE_UNITS: u32 = 80_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapDelegateParams {
+    pub program_id: Pubkey,
+    pub delegator: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Delegated mint, and the swap's output.
+    pub mint: Pubkey,
+    pub input_mint: String,
+    pub in_amount: u64,
+    pub slippage_bps: u16,
+    pub duration_secs: i64,
+    pub rules: SpendingRules,
+    pub auth_pubkey: Vec<u8>,
+}
+
+/// Second step of a two-step run, persisted once the swap has landed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingSwapDelegation {
+    pub swap_signature: String,
+    pub program_id: String,
+    pub delegator: String,
+    pub beneficiary: String,
+    pub mint: String,
+    pub received_amount: u64,
+    pub duration_secs: i64,
+    pub rules: SpendingRules,
+    pub auth_pubkey_b64: String,
+    pub created_ms: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SwapDelegateOutcome {
+    Atomic {
+        signature: String,
+        delegated: u64,
+    },
+    TwoStep {
+        swap_signature: String,
+        delegation_signature: String,
+        delegated: u64,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+fn delegation_instructions(
+    program_id: &Pubkey,
+    delegator: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    duration_secs: i64,
+    rules: &SpendingRules,
+    auth_pubkey: &[u8],
+) -> Result<Vec<JsInstruction>, String> {
+    let now = (js_sys::Date::now() / 1000.0) as i64;
+    let (_, mut instructions) = build_create_delegation_instructions(
+        program_id,
+        delegator,
+        beneficiary,
+        mint,
+        amount,
+        now - 60,
+        now + duration_secs,
+        rules,
+    )?;
+    let (_, auth_ixs) =
+        build_set_auth_instructions(program_id, delegator, beneficiary, mint, auth_pubkey)?;
+    instructions.extend(auth_ixs);
+    Ok(instructions)
+}
+
+async fn ensure_can_delegate(
+    rpc_url: &str,
+    program_id: &Pubkey,
+    delegator: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+) -> Result<(), String> {
+    ensure_not_paused(rpc_url, program_id).await?;
+    let addrs = derive_addresses(program_id, delegator, beneficiary, mint);
+    if get_account_data_base64(rpc_url, &addrs.delegation_pda.to_string())
+        .await
+        .is_ok()
+    {
+        return Err("Delegation already exists for this beneficiary".to_string());
+    }
+    Ok(())
+}
+
+/// Swap and delegation in one unsigned transaction. The delegation is
+/// sized to the swap's minimum output so it is always covered. `None` when
+/// the combined transaction is too large.
+async fn build_atomic(
+    rpc_url: &str,
+    params: &SwapDelegateParams,
+) -> Result<Option<(UnsignedTransaction, u64)>, String> {
+    let mint = params.mint.to_string();
+    let quote = JupiterClient::get_swap_quote(
+        &params.input_mint,
+        &mint,
+        params.in_amount,
+        params.slippage_bps,
+        None,
+    )
+    .await?;
+    let swap = JupiterClient::get_swap_instructions(&quote, &params.delegator.to_string()).await?;
+    let delegated = quote.other_amount_threshold;
+
+    let mut instructions = swap.swap_instructions()?;
+    for ix in delegation_instructions(
+        &params.program_id,
+        &params.delegator,
+        &params.beneficiary,
+        &params.mint,
+        delegated,
+        params.duration_secs,
+        &params.rules,
+        &params.auth_pubkey,
+    )? {
+        instructions.push(instruction_from_js(&ix)?);
+    }
+    instructions.extend(swap.compute_budget(DELEGATION_COMPUTE_UNITS)?);
+
+    let tx = build_unsigned(
+        rpc_url,
+        &params.delegator,
+        &instructions,
+        &swap.address_lookup_table_addresses,
+    )
+    .await?;
+    if !tx.fits() {
+        log::info!(
+            "Swap + delegation is {} bytes, falling back to two steps",
+            tx.size
+        );
+        return Ok(None);
+    }
+    Ok(Some((tx, delegated)))
+}
+
+/// Executes the best available quote on its own.
+async fn swap_step(
+    adapter: &WalletAdapter,
+    rpc_url: &str,
+    params: &SwapDelegateParams,
+) -> Result<(String, u64), String> {
+    let delegator = params.delegator.to_string();
+    let request = QuoteRequest {
+        input_mint: params.input_mint.clone(),
+        output_mint: params.mint.to_string(),
+        amount: params.in_amount,
+        swap_mode: SwapMode::ExactIn,
+        slippage_bps: params.slippage_bps,
+        auto_slippage: false,
+        taker: Some(delegator.clone()),
+    };
+    let providers = default_providers();
+    let results = join_all(
+        providers
+            .iter()
+            .map(|p| fetch_with_timeout(p.as_ref(), &request)),
+    )
+    .await;
+    let quotes: Vec<_> = results
+        .iter()
+        .filter_map(|r| r.quote().cloned())
+        .filter(|q| q.is_executable())
+        .collect();
+    let best = rank_quotes(&quotes, js_sys::Date::now())
+        .into_iter()
+        .find(|r| !r.stale)
+        .ok_or("No executable swap quote available")?;
+
+    let result = execute_quote(adapter, &best.quote, &delegator, rpc_url).await?;
+    Ok((
+        result.signature,
+        result.realized_out.unwrap_or(result.min_out),
+    ))
+}
+
+/// Creates the delegation recorded in `pending`, clearing it on success.
+pub async fn resume(
+    adapter: &WalletAdapter,
+    rpc_url: &str,
+    pending: &PendingSwapDelegation,
+) -> Result<String, String> {
+    let parse = |v: &str| Pubkey::from_str(v).map_err(|e| e.to_string());
+    let program_id = parse(&pending.program_id)?;
+    let delegator = parse(&pending.delegator)?;
+    let beneficiary = parse(&pending.beneficiary)?;
+    let mint = parse(&pending.mint)?;
+    let auth_pubkey = B64
+        .decode(pending.auth_pubkey_b64.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    ensure_can_delegate(rpc_url, &program_id, &delegator, &beneficiary, &mint).await?;
+    let instructions = delegation_instructions(
+        &program_id,
+        &delegator,
+        &beneficiary,
+        &mint,
+        pending.received_amount,
+        pending.duration_secs,
+        &pending.rules,
+        &auth_pubkey,
+    )?;
+    let json = serde_json::to_string(&instructions).map_err(|e| e.to_string())?;
+    let signature = adapter
+        .send_instructions_json(rpc_url, &pending.delegator, &json)
+        .await?;
+    clear_pending();
+    Ok(signature)
+}
+
+pub async fn run(
+    adapter: &WalletAdapter,
+    rpc_url: &str,
+    params: &SwapDelegateParams,
+    mut on_step: impl FnMut(String),
+) -> Result<SwapDelegateOutcome, String> {
+    ensure_can_delegate(
+        rpc_url,
+        &params.program_id,
+        &params.delegator,
+        &params.beneficiary,
+        &params.mint,
+    )
+    .await?;
+
+    on_step("Building swap + delegation transaction...".to_string());
+    // Only a transaction that could not be built falls back to two steps.
+    // Once the wallet is asked to sign, any failure (rejection, send error,
+    // on-chain error, timeout) is returned so nothing is swapped twice.
+    let atomic = match build_atomic(rpc_url, params).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            log::warn!("Atomic swap + delegation unavailable: {}", e);
+            None
+        }
+    };
+    if let Some((tx, delegated)) = atomic {
+        let signed = adapter.sign_transaction_base64(&tx.base64).await?;
+        let signature = send_transaction_base64(rpc_url, &signed).await?;
+        wait_for_confirmation(rpc_url, &signature, CONFIRM_TIMEOUT_MS).await?;
+        return Ok(SwapDelegateOutcome::Atomic {
+            signature,
+            delegated,
+        });
+    }
+
+    on_step("Step 1/2: swapping...".to_string());
+    let (swap_signature, received) = swap_step(adapter, rpc_url, params).await?;
+    let pending = PendingSwapDelegation {
+        swap_signature: swap_signature.clone(),
+        program_id: params.program_id.to_string(),
+        delegator: params.delegator.to_string(),
+        beneficiary: params.beneficiary.to_string(),
+        mint: params.mint.to_string(),
+        received_amount: received,
+        duration_secs: params.duration_secs,
+        rules: params.rules.clone(),
+        auth_pubkey_b64: B64.encode(&params.auth_pubkey),
+        created_ms: js_sys::Date::now(),
+    };
+    save_pending(&pending);
+
+    on_step("Step 2/2: creating delegation...".to_string());
+    let delegation_signature = resume(adapter, rpc_url, &pending).await?;
+    Ok(SwapDelegateOutcome::TwoStep {
+        swap_signature,
+        delegation_signature,
+        delegated: received,
+    })
+}
+
+pub fn load_pending() -> Option<PendingSwapDelegation> {
+    PENDING.load()
+}
+
+fn save_pending(pending: &PendingSwapDelegation) {
+    PENDING.save(pending);
+}
+
+pub fn clear_pending() {
+    PENDING.clear();
+}
@@ -1,12 +1,13 @@
-use base64::engine::general_purpose::STANDARD as B64;
+use base64::engine::general_purpose::{STANDARD as B64, URL_SAFE_NO_PAD as B64URL};
 use base64::Engine;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    system_program, sysvar,
+    sysvar,
 };
+use solana_sdk_ids::system_program;
 use std::str::FromStr;
 
 pub const DEFAULT_PROGRAM_ID: &str = "de1gMWmVGZxacWBjpa6HqCfRG9fxcmkGqGdZKJVq5H9";
@@ -14,21 +15,56 @@ pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
 pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
 pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
 pub const SECP256R1_PROGRAM_ID: &str = "Secp256r1SigVerify1111111111111111111111111";
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JsInstruction {
     pub program_id: String,
     pub keys: Vec<JsAccountMeta>,
     pub data: String,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JsAccountMeta {
     pub pubkey: String,
     pub is_signer: bool,
     pub is_writable: bool,
 }
 
+/// Optional per-withdraw limits enforced by the program on top of the
+/// delegation's total `max_amount` and time window. Zero disables a rule.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingRules {
+    pub max_per_withdraw: u64,
+    pub min_interval_secs: i64,
+    pub window_secs: i64,
+    pub window_cap: u64,
+}
+
+impl SpendingRules {
+    pub fn is_unrestricted(&self) -> bool {
+        self.max_per_withdraw == 0
+            && self.min_interval_secs <= 0
+            && (self.window_secs <= 0 || self.window_cap == 0)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelegationState {
+    pub nonce: u64,
+    pub rules: SpendingRules,
+    pub last_withdraw_ts: i64,
+    pub window_start_ts: i64,
+    pub window_withdrawn: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpendingViolation {
+    MaxPerWithdraw { max: u64 },
+    Cooldown { next_allowed_ts: i64 },
+    WindowCap { cap: u64, remaining: u64, resets_ts: i64 },
+}
+
 pub struct DelegationAddresses {
     pub source_ata: Pubkey,
     pub destination_ata: Pubkey,
@@ -64,6 +100,8 @@ pub fn derive_addresses(
     }
 }
 
+// One argument per account or data field of the instruction.
+#[allow(clippy::too_many_arguments)]
 pub fn build_create_delegation_instructions(
     program_id: &Pubkey,
     delegator: &Pubkey,
@@ -72,7 +110,14 @@ pub fn build_create_delegation_instructions(
     max_amount: u64,
     start_ts: i64,
     end_ts: i64,
+    rules: &SpendingRules,
 ) -> Result<(DelegationAddresses, Vec<JsInstruction>), String> {
+    if rules.max_per_withdraw > max_amount {
+        return Err("max per withdraw exceeds delegation amount".to_string());
+    }
+    if rules.window_cap > 0 && rules.window_secs <= 0 {
+        return Err("window cap requires a window length".to_string());
+    }
     let addrs = derive_addresses(program_id, delegator, beneficiary, mint);
 
     let create_source_ata =
@@ -90,11 +135,18 @@ pub fn build_create_delegation_instructions(
         data: build_token_approve_data(max_amount),
     };
 
-    let mut data = Vec::with_capacity(1 + 8 + 8 + 8);
+    let mut data = Vec::with_capacity(1 + 8 + 8 + 8 + 32);
     data.push(2);
     data.extend_from_slice(&start_ts.to_le_bytes());
     data.extend_from_slice(&end_ts.to_le_bytes());
     data.extend_from_slice(&max_amount.to_le_bytes());
+    // Legacy layout when no rules are set, so older program versions still accept it.
+    if !rules.is_unrestricted() {
+        data.extend_from_slice(&rules.max_per_withdraw.to_le_bytes());
+        data.extend_from_slice(&rules.min_interval_secs.to_le_bytes());
+        data.extend_from_slice(&rules.window_secs.to_le_bytes());
+        data.extend_from_slice(&rules.window_cap.to_le_bytes());
+    }
 
     let create_ix = Instruction {
         program_id: *program_id,
@@ -152,6 +204,8 @@ pub fn build_set_auth_instructions(
     Ok((addrs, vec![instruction_to_js(&set_auth_ix)]))
 }
 
+// One argument per account or data field of the instruction.
+#[allow(clippy::too_many_arguments)]
 pub fn build_withdraw_instructions(
     program_id: &Pubkey,
     delegator: &Pubkey,
@@ -164,6 +218,7 @@ pub fn build_withdraw_instructions(
     authenticator_data: &[u8],
     client_data_json: &[u8],
     signature: &[u8],
+    fee_recipient: Option<&Pubkey>,
 ) -> Result<(DelegationAddresses, Vec<JsInstruction>), String> {
     if signature.len() != 64 {
         return Err("signature must be 64 bytes".to_string());
@@ -182,6 +237,7 @@ pub fn build_withdraw_instructions(
         nonce,
         auth_expiry_ts,
     );
+    check_challenge(client_data_json, &message)?;
 
     let webauthn_message = build_webauthn_message(authenticator_data, client_data_json);
     let secp_ix = build_secp256r1_instruction(auth_pubkey, signature, &webauthn_message)?;
@@ -203,7 +259,7 @@ pub fn build_withdraw_instructions(
     data.extend_from_slice(authenticator_data);
     data.extend_from_slice(client_data_json);
 
-    let withdraw_ix = Instruction {
+    let mut withdraw_ix = Instruction {
         program_id: *program_id,
         accounts: vec![
             AccountMeta::new_readonly(*beneficiary, true),
@@ -219,6 +275,13 @@ pub fn build_withdraw_instructions(
         ],
         data,
     };
+    // The program takes its fee into this account when the config charges
+    // one; it must already exist.
+    if let Some(recipient) = fee_recipient {
+        withdraw_ix
+            .accounts
+            .push(AccountMeta::new(associated_token_address(recipient, mint), false));
+    }
 
     let instructions = vec![instruction_to_js(&secp_ix), instruction_to_js(&withdraw_ix)];
     Ok((addrs, instructions))
@@ -266,6 +329,34 @@ fn nibble_to_hex(n: u8) -> u8 {
     }
 }
 
+pub fn parse_amount(value: &str, decimals: u8) -> Result<u64, String> {
+    let mut parts = value.split('.');
+    let whole = parts.next().unwrap_or("0");
+    let frac = parts.next();
+    if parts.next().is_some() {
+        return Err("invalid amount format".to_string());
+    }
+
+    let whole_val: u64 = whole.parse().map_err(|_| "invalid number")?;
+    let scale = 10u64.pow(decimals as u32);
+    let mut amount = whole_val
+        .checked_mul(scale)
+        .ok_or("amount overflow")?;
+
+    if let Some(frac_str) = frac {
+        if frac_str.len() > decimals as usize {
+            return Err("too many decimal places".to_string());
+        }
+        let frac_val: u64 = if frac_str.is_empty() { 0 } else { frac_str.parse().map_err(|_| "invalid fraction")? };
+        let frac_scale = 10u64.pow((decimals as usize - frac_str.len()) as u32);
+        amount = amount
+            .checked_add(frac_val * frac_scale)
+            .ok_or("amount overflow")?;
+    }
+
+    Ok(amount)
+}
+
 pub fn encode_message_base64(message: &[u8]) -> String {
     B64.encode(message)
 }
@@ -282,6 +373,72 @@ pub fn parse_nonce_from_state(data: &[u8]) -> Result<u64, String> {
     Ok(u64::from_le_bytes(bytes))
 }
 
+/// Decodes the delegation account. Accounts created without spending rules
+/// end at the nonce and decode with unrestricted rules.
+pub fn parse_delegation_state(data: &[u8]) -> Result<DelegationState, String> {
+    let nonce = parse_nonce_from_state(data)?;
+    if data.len() < 220 {
+        return Ok(DelegationState {
+            nonce,
+            ..Default::default()
+        });
+    }
+    Ok(DelegationState {
+        nonce,
+        rules: SpendingRules {
+            max_per_withdraw: read_u64(data, 164),
+            min_interval_secs: read_u64(data, 172) as i64,
+            window_secs: read_u64(data, 180) as i64,
+            window_cap: read_u64(data, 188),
+        },
+        last_withdraw_ts: read_u64(data, 196) as i64,
+        window_start_ts: read_u64(data, 204) as i64,
+        window_withdrawn: read_u64(data, 212),
+    })
+}
+
+/// Mirrors the program's per-withdraw checks so the UI can reject a pull
+/// before asking for a passkey signature.
+pub fn check_spending_rules(state: &DelegationState, amount: u64, now: i64) -> Vec<SpendingViolation> {
+    let rules = &state.rules;
+    let mut out = Vec::new();
+    if rules.max_per_withdraw > 0 && amount > rules.max_per_withdraw {
+        out.push(SpendingViolation::MaxPerWithdraw {
+            max: rules.max_per_withdraw,
+        });
+    }
+    if rules.min_interval_secs > 0 && state.last_withdraw_ts > 0 {
+        let next_allowed_ts = state.last_withdraw_ts.saturating_add(rules.min_interval_secs);
+        if now < next_allowed_ts {
+            out.push(SpendingViolation::Cooldown { next_allowed_ts });
+        }
+    }
+    if rules.window_secs > 0 && rules.window_cap > 0 {
+        let window_end_ts = state.window_start_ts.saturating_add(rules.window_secs);
+        let window_open = now < window_end_ts;
+        let used = if window_open { state.window_withdrawn } else { 0 };
+        let remaining = rules.window_cap.saturating_sub(used);
+        if amount > remaining {
+            let resets_ts = if window_open {
+                window_end_ts
+            } else {
+                now.saturating_add(rules.window_secs)
+            };
+            out.push(SpendingViolation::WindowCap {
+                cap: rules.window_cap,
+                remaining,
+                resets_ts,
+            });
+        }
+    }
+    out
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let bytes: [u8; 8] = data[offset..offset + 8].try_into().unwrap();
+    u64::from_le_bytes(bytes)
+}
+
 pub fn instruction_to_js(ix: &Instruction) -> JsInstruction {
     JsInstruction {
         program_id: ix.program_id.to_string(),
@@ -298,6 +455,24 @@ pub fn instruction_to_js(ix: &Instruction) -> JsInstruction {
     }
 }
 
+pub fn instruction_from_js(ix: &JsInstruction) -> Result<Instruction, String> {
+    let program_id = Pubkey::from_str(&ix.program_id).map_err(|e| e.to_string())?;
+    let mut accounts = Vec::with_capacity(ix.keys.len());
+    for key in &ix.keys {
+        let pubkey = Pubkey::from_str(&key.pubkey).map_err(|e| e.to_string())?;
+        accounts.push(AccountMeta {
+            pubkey,
+            is_signer: key.is_signer,
+            is_writable: key.is_writable,
+        });
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data: decode_base64(&ix.data)?,
+    })
+}
+
 pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
     let ata_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).unwrap();
     let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
@@ -308,6 +483,22 @@ pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
     .0
 }
 
+/// The passkey signs sha256 of the withdraw message as its WebAuthn
+/// challenge. A signature made for another amount, nonce or expiry would be
+/// rejected on-chain, so refuse it before the wallet is asked to sign.
+fn check_challenge(client_data_json: &[u8], message: &[u8]) -> Result<(), String> {
+    let client_data: serde_json::Value =
+        serde_json::from_slice(client_data_json).map_err(|_| "client_data_json is not JSON")?;
+    let challenge = client_data
+        .get("challenge")
+        .and_then(|v| v.as_str())
+        .ok_or("client_data_json has no challenge")?;
+    if challenge.trim_end_matches('=') != B64URL.encode(Sha256::digest(message)) {
+        return Err("passkey signature does not cover this amount, nonce and expiry".to_string());
+    }
+    Ok(())
+}
+
 fn build_webauthn_message(authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
     let mut hasher = Sha256::new();
     hasher.update(client_data_json);
@@ -359,6 +550,15 @@ fn build_secp256r1_instruction(
     })
 }
 
+pub fn build_memo_instruction(signer: &Pubkey, memo: &str) -> JsInstruction {
+    let ix = Instruction {
+        program_id: Pubkey::from_str(MEMO_PROGRAM_ID).unwrap(),
+        accounts: vec![AccountMeta::new_readonly(*signer, true)],
+        data: memo.as_bytes().to_vec(),
+    };
+    instruction_to_js(&ix)
+}
+
 fn build_token_approve_data(amount: u64) -> Vec<u8> {
     // SPL Token Approve instruction = 4
     let mut data = Vec::with_capacity(1 + 8);
@@ -388,3 +588,204 @@ fn build_create_ata_idempotent_ix(
         data: vec![1u8], // CreateIdempotent
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn account_data(nonce: u64, tail: &[u64]) -> Vec<u8> {
+        let mut data = vec![0u8; 156];
+        data.extend_from_slice(&nonce.to_le_bytes());
+        for value in tail {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    fn state(rules: SpendingRules) -> DelegationState {
+        DelegationState {
+            nonce: 3,
+            rules,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_state_with_rules() {
+        let data = account_data(
+            3,
+            &[5_000_000, 600, 86_400, 20_000_000, 1_699_999_000, 1_699_990_000, 7_000_000],
+        );
+        assert_eq!(
+            parse_delegation_state(&data).unwrap(),
+            DelegationState {
+                nonce: 3,
+                rules: SpendingRules {
+                    max_per_withdraw: 5_000_000,
+                    min_interval_secs: 600,
+                    window_secs: 86_400,
+                    window_cap: 20_000_000,
+                },
+                last_withdraw_ts: 1_699_999_000,
+                window_start_ts: 1_699_990_000,
+                window_withdrawn: 7_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn legacy_accounts_have_no_rules() {
+        let parsed = parse_delegation_state(&account_data(9, &[])).unwrap();
+        assert_eq!(parsed.nonce, 9);
+        assert!(parsed.rules.is_unrestricted());
+        assert!(parse_delegation_state(&[0u8; 163]).is_err());
+    }
+
+    #[test]
+    fn unrestricted_state_allows_any_amount() {
+        assert!(check_spending_rules(&state(SpendingRules::default()), u64::MAX, NOW).is_empty());
+    }
+
+    #[test]
+    fn max_per_withdraw() {
+        let s = state(SpendingRules {
+            max_per_withdraw: 1_000,
+            ..Default::default()
+        });
+        assert!(check_spending_rules(&s, 1_000, NOW).is_empty());
+        assert_eq!(
+            check_spending_rules(&s, 1_001, NOW),
+            vec![SpendingViolation::MaxPerWithdraw { max: 1_000 }]
+        );
+    }
+
+    #[test]
+    fn cooldown_starts_after_first_withdraw() {
+        let mut s = state(SpendingRules {
+            min_interval_secs: 600,
+            ..Default::default()
+        });
+        assert!(check_spending_rules(&s, 1, NOW).is_empty());
+        s.last_withdraw_ts = NOW - 599;
+        assert_eq!(
+            check_spending_rules(&s, 1, NOW),
+            vec![SpendingViolation::Cooldown { next_allowed_ts: NOW + 1 }]
+        );
+        s.last_withdraw_ts = NOW - 600;
+        assert!(check_spending_rules(&s, 1, NOW).is_empty());
+    }
+
+    #[test]
+    fn window_cap_counts_only_the_open_window() {
+        let mut s = state(SpendingRules {
+            window_secs: 86_400,
+            window_cap: 10_000,
+            ..Default::default()
+        });
+        s.window_start_ts = NOW - 3_600;
+        s.window_withdrawn = 8_000;
+        assert!(check_spending_rules(&s, 2_000, NOW).is_empty());
+        assert_eq!(
+            check_spending_rules(&s, 2_001, NOW),
+            vec![SpendingViolation::WindowCap {
+                cap: 10_000,
+                remaining: 2_000,
+                resets_ts: NOW - 3_600 + 86_400,
+            }]
+        );
+        // Once the window has lapsed the full cap is available again.
+        s.window_start_ts = NOW - 86_400;
+        assert!(check_spending_rules(&s, 10_000, NOW).is_empty());
+        assert_eq!(
+            check_spending_rules(&s, 10_001, NOW),
+            vec![SpendingViolation::WindowCap {
+                cap: 10_000,
+                remaining: 10_000,
+                resets_ts: NOW + 86_400,
+            }]
+        );
+    }
+
+    #[test]
+    fn oversized_rules_saturate_instead_of_overflowing() {
+        let mut s = state(SpendingRules {
+            min_interval_secs: i64::MAX,
+            window_secs: i64::MAX,
+            window_cap: 1,
+            ..Default::default()
+        });
+        s.last_withdraw_ts = NOW;
+        s.window_start_ts = NOW;
+        s.window_withdrawn = 1;
+        assert_eq!(
+            check_spending_rules(&s, 1, NOW),
+            vec![
+                SpendingViolation::Cooldown { next_allowed_ts: i64::MAX },
+                SpendingViolation::WindowCap {
+                    cap: 1,
+                    remaining: 0,
+                    resets_ts: i64::MAX,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_requires_a_matching_challenge() {
+        let key = |b: u8| Pubkey::new_from_array([b; 32]);
+        let (program_id, delegator, beneficiary, mint) = (key(1), key(2), key(3), key(4));
+        let addrs = derive_addresses(&program_id, &delegator, &beneficiary, &mint);
+        let message = build_withdraw_message(
+            &program_id,
+            &addrs.delegation_pda,
+            &addrs.source_ata,
+            &addrs.destination_ata,
+            1_000,
+            3,
+            NOW,
+        );
+        let client_data = |challenge: String| {
+            format!(r#"{{"type":"webauthn.get","challenge":"{}"}}"#, challenge).into_bytes()
+        };
+        let signed = client_data(B64URL.encode(Sha256::digest(&message)));
+        let build = |amount: u64, client_data_json: &[u8]| {
+            build_withdraw_instructions(
+                &program_id,
+                &delegator,
+                &beneficiary,
+                &mint,
+                amount,
+                3,
+                NOW,
+                &[2; 33],
+                &[0; 37],
+                client_data_json,
+                &[1; 64],
+                None,
+            )
+        };
+        assert!(build(1_000, &signed).is_ok());
+        assert_eq!(
+            build(1_001, &signed).err().as_deref(),
+            Some("passkey signature does not cover this amount, nonce and expiry")
+        );
+        assert!(check_challenge(&client_data("AAAA".to_string()), &message).is_err());
+        assert!(check_challenge(b"not json", &message).is_err());
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut s = state(SpendingRules {
+            max_per_withdraw: 100,
+            min_interval_secs: 60,
+            window_secs: 3_600,
+            window_cap: 150,
+        });
+        s.last_withdraw_ts = NOW - 10;
+        s.window_start_ts = NOW - 10;
+        s.window_withdrawn = 100;
+        assert_eq!(check_spending_rules(&s, 200, NOW).len(), 3);
+    }
+}
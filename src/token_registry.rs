@@ -0,0 +1,201 @@
+// Known SPL tokens for the swap UI: a bundled default list, search, custom
+// mints resolved over RPC, and a short recently-used list kept in
+// localStorage.
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::rpc::get_account_data_base64;
+use crate::storage::Key;
+use crate::timed_delegation::decode_base64;
+
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+const RECENT_TOKENS: Key<Vec<Token>> = Key::new("td_recent_tokens", 1);
+const MAX_RECENT_TOKENS: usize = 6;
+/// SPL mint layout: `decimals` at byte 44, `is_initialized` at byte 45.
+/// Token-2022 mints share the same base layout.
+const MINT_DECIMALS_OFFSET: usize = 44;
+const MINT_INITIALIZED_OFFSET: usize = 45;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub mint: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub logo_uri: Option<String>,
+}
+
+impl Token {
+    fn known(mint: &str, symbol: &str, name: &str, decimals: u8) -> Self {
+        Self {
+            mint: mint.to_string(),
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            decimals,
+            logo_uri: Some(format!(
+                "https://raw.githubusercontent.com/solana-labs/token-list/main/assets/mainnet/{}/logo.png",
+                mint
+            )),
+        }
+    }
+
+    pub fn sol() -> Self {
+        Self::known(SOL_MINT, "SOL", "Solana", 9)
+    }
+
+    pub fn usdc() -> Self {
+        Self::known(USDC_MINT, "USDC", "USD Coin", 6)
+    }
+
+    /// Short label for mints that are not in the registry.
+    pub fn short_mint(&self) -> String {
+        format!("{}…{}", &self.mint[..4], &self.mint[self.mint.len() - 4..])
+    }
+}
+
+pub fn default_tokens() -> Vec<Token> {
+    vec![
+        Token::sol(),
+        Token::usdc(),
+        Token::known("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "USDT", "USDT", 6),
+        Token::known("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", "JUP", "Jupiter", 6),
+        Token::known("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "BONK", "Bonk", 5),
+        Token::known("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", "mSOL", "Marinade staked SOL", 9),
+        Token::known("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn", "JitoSOL", "Jito Staked SOL", 9),
+        Token::known("EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm", "WIF", "dogwifhat", 6),
+        Token::known("HZ1JovNiVvGrGNiiYvEozEVgZ58xaU3RKwX8eACQBCt3", "PYTH", "Pyth Network", 6),
+        Token::known("4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R", "RAY", "Raydium", 6),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenRegistry {
+    tokens: Vec<Token>,
+    recent: Vec<Token>,
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self {
+            tokens: default_tokens(),
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl TokenRegistry {
+    /// Default list plus whatever was recently used in this browser.
+    pub fn load() -> Self {
+        let mut registry = Self {
+            recent: load_recent(),
+            ..Self::default()
+        };
+        for token in registry.recent.clone() {
+            registry.insert(token);
+        }
+        registry
+    }
+
+    pub fn recent(&self) -> &[Token] {
+        &self.recent
+    }
+
+    pub fn by_mint(&self, mint: &str) -> Option<&Token> {
+        self.tokens.iter().find(|t| t.mint == mint)
+    }
+
+    pub fn insert(&mut self, token: Token) {
+        if self.by_mint(&token.mint).is_none() {
+            self.tokens.push(token);
+        }
+    }
+
+    /// Case-insensitive match on symbol, name or mint. Exact symbol matches
+    /// come first, then prefix matches, then everything else.
+    pub fn search(&self, query: &str) -> Vec<Token> {
+        let q = query.trim().to_lowercase();
+        if q.is_empty() {
+            return self.tokens.clone();
+        }
+        let mut hits: Vec<(u8, &Token)> = self
+            .tokens
+            .iter()
+            .filter_map(|t| {
+                let symbol = t.symbol.to_lowercase();
+                let rank = if symbol == q || t.mint.to_lowercase() == q {
+                    0
+                } else if symbol.starts_with(&q) {
+                    1
+                } else if symbol.contains(&q)
+                    || t.name.to_lowercase().contains(&q)
+                    || t.mint.to_lowercase().starts_with(&q)
+                {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, t))
+            })
+            .collect();
+        hits.sort_by_key(|(rank, _)| *rank);
+        hits.into_iter().map(|(_, t)| t.clone()).collect()
+    }
+
+    /// Moves `token` to the front of the recent list and persists it.
+    pub fn mark_used(&mut self, token: &Token) {
+        self.insert(token.clone());
+        self.recent.retain(|t| t.mint != token.mint);
+        self.recent.insert(0, token.clone());
+        self.recent.truncate(MAX_RECENT_TOKENS);
+        save_recent(&self.recent);
+    }
+}
+
+/// Reads decimals from an on-chain mint account.
+pub fn parse_mint_decimals(data: &[u8]) -> Result<u8, String> {
+    if data.len() <= MINT_INITIALIZED_OFFSET {
+        return Err("account is not a token mint".to_string());
+    }
+    if data[MINT_INITIALIZED_OFFSET] != 1 {
+        return Err("mint is not initialized".to_string());
+    }
+    Ok(data[MINT_DECIMALS_OFFSET])
+}
+
+/// Resolves a pasted mint address, using the registry when it is known and
+/// fetching decimals over RPC otherwise.
+pub async fn resolve_mint(
+    registry: &TokenRegistry,
+    rpc_url: &str,
+    mint: &str,
+) -> Result<Token, String> {
+    let mint = mint.trim();
+    if Pubkey::from_str(mint).is_err() {
+        return Err(format!("'{}' is not a valid mint address", mint));
+    }
+    if let Some(token) = registry.by_mint(mint) {
+        return Ok(token.clone());
+    }
+    let data = decode_base64(&get_account_data_base64(rpc_url, mint).await?)?;
+    let decimals = parse_mint_decimals(&data)?;
+    let mut token = Token {
+        mint: mint.to_string(),
+        symbol: String::new(),
+        name: "Unknown token".to_string(),
+        decimals,
+        logo_uri: None,
+    };
+    token.symbol = token.short_mint();
+    Ok(token)
+}
+
+fn load_recent() -> Vec<Token> {
+    RECENT_TOKENS.load().unwrap_or_default()
+}
+
+fn save_recent(tokens: &[Token]) {
+    RECENT_TOKENS.save(&tokens.to_vec());
+}
@@ -0,0 +1,86 @@
+// Unsigned v0 transactions with address lookup tables, for flows that
+// compose aggregator swap instructions with our own.
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, AddressLookupTableAccount, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+
+use crate::rpc::{get_account_data_base64, get_latest_blockhash};
+use crate::timed_delegation::decode_base64;
+
+/// Lookup table accounts start with a fixed-size metadata header.
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+pub fn parse_lookup_table(key: Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount, String> {
+    if data.len() < LOOKUP_TABLE_META_SIZE || !(data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32) {
+        return Err(format!("{} is not an address lookup table", key));
+    }
+    let addresses = data[LOOKUP_TABLE_META_SIZE..]
+        .chunks_exact(32)
+        .map(|chunk| Pubkey::new_from_array(chunk.try_into().unwrap()))
+        .collect();
+    Ok(AddressLookupTableAccount { key, addresses })
+}
+
+pub async fn fetch_lookup_tables(
+    rpc_url: &str,
+    addresses: &[String],
+) -> Result<Vec<AddressLookupTableAccount>, String> {
+    let mut tables = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        let key = Pubkey::from_str(address).map_err(|e| e.to_string())?;
+        let data = decode_base64(&get_account_data_base64(rpc_url, address).await?)?;
+        tables.push(parse_lookup_table(key, &data)?);
+    }
+    Ok(tables)
+}
+
+/// An unsigned v0 transaction ready for the wallet.
+pub struct UnsignedTransaction {
+    pub base64: String,
+    pub size: usize,
+}
+
+impl UnsignedTransaction {
+    pub fn fits(&self) -> bool {
+        self.size <= PACKET_DATA_SIZE
+    }
+}
+
+pub fn compile_unsigned(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<UnsignedTransaction, String> {
+    let message = v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)
+        .map_err(|e| format!("failed to compile transaction: {}", e))?;
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+        message: VersionedMessage::V0(message),
+    };
+    let bytes = bincode::serialize(&tx).map_err(|e| e.to_string())?;
+    Ok(UnsignedTransaction {
+        size: bytes.len(),
+        base64: B64.encode(bytes),
+    })
+}
+
+/// Compiles against the latest blockhash and lookup tables fetched over RPC.
+pub async fn build_unsigned(
+    rpc_url: &str,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_table_addresses: &[String],
+) -> Result<UnsignedTransaction, String> {
+    let lookup_tables = fetch_lookup_tables(rpc_url, lookup_table_addresses).await?;
+    let blockhash =
+        Hash::from_str(&get_latest_blockhash(rpc_url).await?).map_err(|e| e.to_string())?;
+    compile_unsigned(payer, instructions, &lookup_tables, blockhash)
+}
@@ -0,0 +1,103 @@
+// Passkey-signed withdraw authorization that can be carried from the
+// delegator's device to the beneficiary (e.g. a merchant terminal) and
+// submitted there without the passkey.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as B64URL;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+pub const VOUCHER_PREFIX: &str = "tdv1:";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawVoucher {
+    pub delegator: String,
+    pub beneficiary: String,
+    pub mint: String,
+    pub amount: u64,
+    pub nonce: u64,
+    pub auth_expiry_ts: i64,
+    pub auth_pubkey: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl WithdrawVoucher {
+    pub fn encode(&self) -> Result<String, String> {
+        let bytes = rmp_serde::to_vec(self).map_err(|e| e.to_string())?;
+        Ok(format!("{}{}", VOUCHER_PREFIX, B64URL.encode(bytes)))
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let body = text
+            .trim()
+            .strip_prefix(VOUCHER_PREFIX)
+            .ok_or("not a withdraw voucher")?;
+        let bytes = B64URL.decode(body.as_bytes()).map_err(|e| e.to_string())?;
+        rmp_serde::from_slice(&bytes).map_err(|e| format!("invalid voucher: {}", e))
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.auth_expiry_ts
+    }
+
+    /// A voucher signs one exact amount against the delegation's current
+    /// nonce; anything else would fail on-chain after the wallet prompt.
+    pub fn check_against(&self, amount: u64, nonce: u64) -> Result<(), String> {
+        if self.amount != amount {
+            return Err("amount differs from the signed voucher".to_string());
+        }
+        if self.nonce != nonce {
+            return Err("voucher was signed for an earlier nonce and has been used or superseded".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voucher() -> WithdrawVoucher {
+        WithdrawVoucher {
+            delegator: "Dele111111111111111111111111111111111111111".to_string(),
+            beneficiary: "Bene111111111111111111111111111111111111111".to_string(),
+            mint: "Mint111111111111111111111111111111111111111".to_string(),
+            amount: 4_200_000,
+            nonce: 7,
+            auth_expiry_ts: 1_700_000_120,
+            auth_pubkey: vec![2; 33],
+            authenticator_data: vec![1; 37],
+            client_data_json: br#"{"type":"webauthn.get"}"#.to_vec(),
+            signature: vec![9; 64],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let encoded = voucher().encode().unwrap();
+        assert!(encoded.starts_with(VOUCHER_PREFIX));
+        assert_eq!(WithdrawVoucher::decode(&format!("  {}\n", encoded)).unwrap(), voucher());
+    }
+
+    #[test]
+    fn rejects_foreign_or_corrupt_text() {
+        assert!(WithdrawVoucher::decode("solana:abc").is_err());
+        assert!(WithdrawVoucher::decode("tdv1:!!!").is_err());
+        assert!(WithdrawVoucher::decode("tdv1:AAAA").is_err());
+    }
+
+    #[test]
+    fn expires_at_the_deadline() {
+        let v = voucher();
+        assert!(!v.is_expired(v.auth_expiry_ts - 1));
+        assert!(v.is_expired(v.auth_expiry_ts));
+    }
+
+    #[test]
+    fn checks_amount_and_nonce() {
+        let v = voucher();
+        assert!(v.check_against(4_200_000, 7).is_ok());
+        assert!(v.check_against(4_200_001, 7).is_err());
+        assert!(v.check_against(4_200_000, 8).is_err());
+    }
+}
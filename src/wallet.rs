@@ -1,13 +1,65 @@
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
 use serde::Deserialize;
 use serde_wasm_bindgen::from_value;
+use solana_sdk::pubkey::Pubkey;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
-// Simplified wallet adapter for browser wallets
+use crate::config;
+use crate::rpc::{send_transaction_base64, wait_for_confirmation};
+use crate::storage;
+use crate::timed_delegation::{instruction_from_js, JsInstruction};
+use crate::v0_transaction::build_unsigned;
+use crate::wallet_standard::{self, account_address, StandardWallet};
+
+const CONFIRM_TIMEOUT_MS: f64 = 60_000.0;
+
+// Browser wallet adapter. Wallet Standard wallets are signed through
+// directly; a bare `window.solana` injection goes through the `td` helpers.
 #[derive(Clone, Debug)]
 pub struct WalletAdapter {
     connected: bool,
     public_key: Option<String>,
+    standard: Option<StandardWallet>,
+    account: Option<JsValue>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WalletChoice {
+    Standard(StandardWallet),
+    /// Legacy `window.solana` provider, for wallets not on Wallet Standard.
+    Injected,
+}
+
+impl WalletChoice {
+    pub fn available() -> Vec<WalletChoice> {
+        let mut choices: Vec<_> = wallet_standard::wallets()
+            .into_iter()
+            .map(WalletChoice::Standard)
+            .collect();
+        if choices.is_empty() && WalletAdapter::is_installed() {
+            choices.push(WalletChoice::Injected);
+        }
+        choices
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            WalletChoice::Standard(w) => w.name.clone(),
+            WalletChoice::Injected => "Browser wallet".to_string(),
+        }
+    }
+
+    pub fn icon(&self) -> Option<String> {
+        match self {
+            WalletChoice::Standard(w) => w.icon.clone(),
+            WalletChoice::Injected => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -42,6 +94,8 @@ impl WalletAdapter {
         Self {
             connected: false,
             public_key: None,
+            standard: None,
+            account: None,
         }
     }
 
@@ -54,7 +108,9 @@ impl WalletAdapter {
         false
     }
 
-    pub async fn connect_wallet() -> Result<String, String> {
+    /// With `only_if_trusted`, resolves without a prompt only if the user
+    /// already approved this site.
+    async fn connect_injected(only_if_trusted: bool) -> Result<String, String> {
         let window = web_sys::window().ok_or("window not available")?;
         let solana = js_sys::Reflect::get(&window, &JsValue::from_str("solana"))
             .map_err(|_| "Wallet not found")?;
@@ -71,7 +127,13 @@ impl WalletAdapter {
             .dyn_into()
             .map_err(|_| "Connect is not a function")?;
 
-        let result = connect_fn.call0(&solana);
+        let result = if only_if_trusted {
+            let options = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&options, &"onlyIfTrusted".into(), &JsValue::TRUE);
+            connect_fn.call1(&solana, &options)
+        } else {
+            connect_fn.call0(&solana)
+        };
         
         // Wait for promise
         let promise: js_sys::Promise = result
@@ -103,16 +165,126 @@ impl WalletAdapter {
         Ok(pubkey_str)
     }
 
-    pub async fn connect(&mut self) -> Result<String, String> {
-        let pubkey_str = Self::connect_wallet().await?;
+    async fn connect_injected_account(&mut self, only_if_trusted: bool) -> Result<String, String> {
+        let pubkey_str = Self::connect_injected(only_if_trusted).await?;
         self.connected = true;
         self.public_key = Some(pubkey_str.clone());
+        self.standard = None;
+        self.account = None;
         Ok(pubkey_str)
     }
 
+    pub async fn connect_choice(&mut self, choice: WalletChoice) -> Result<String, String> {
+        self.connect_choice_with(choice, false).await
+    }
+
+    /// Reconnects a previously approved wallet without prompting; fails if
+    /// the wallet would need to ask the user.
+    pub async fn reconnect_choice(&mut self, choice: WalletChoice) -> Result<String, String> {
+        self.connect_choice_with(choice, true).await
+    }
+
+    async fn connect_choice_with(&mut self, choice: WalletChoice, silent: bool) -> Result<String, String> {
+        let WalletChoice::Standard(wallet) = choice else {
+            return self.connect_injected_account(silent).await;
+        };
+        let account = wallet.connect(silent).await?;
+        let address = account_address(&account).ok_or("wallet account has no address")?;
+        self.connected = true;
+        self.public_key = Some(address.clone());
+        self.standard = Some(wallet);
+        self.account = Some(account);
+        Ok(address)
+    }
+
+    pub fn wallet_name(&self) -> Option<String> {
+        match &self.standard {
+            Some(wallet) => Some(wallet.name.clone()),
+            None if self.connected => Some(WalletChoice::Injected.name()),
+            None => None,
+        }
+    }
+
+    /// Follows account switches and disconnects, through `standard:events`
+    /// or the injected provider's `accountChanged`/`disconnect` events. Pass
+    /// each account to `set_account`. Returns the unsubscribe function, or
+    /// `None` if the wallet emits no events.
+    pub fn watch_account(
+        &self,
+        listener: impl FnMut(Option<JsValue>) + 'static,
+    ) -> Option<js_sys::Function> {
+        match &self.standard {
+            Some(wallet) => wallet.on_accounts_changed(listener),
+            None if self.connected => watch_injected(listener),
+            None => None,
+        }
+    }
+
+    /// Switches to `account`, or to disconnected for `None`. Returns the new
+    /// address.
+    pub fn set_account(&mut self, account: Option<JsValue>) -> Option<String> {
+        let address = account.as_ref().and_then(|a| {
+            account_address(a).or_else(|| {
+                // Injected providers pass a web3.js PublicKey.
+                js_sys::Reflect::get(a, &JsValue::from_str("toBase58"))
+                    .ok()?
+                    .dyn_into::<js_sys::Function>()
+                    .ok()?
+                    .call0(a)
+                    .ok()?
+                    .as_string()
+            })
+        });
+        self.connected = address.is_some();
+        self.public_key = address.clone();
+        self.account = account.filter(|_| address.is_some() && self.standard.is_some());
+        address
+    }
+
+    /// `None` for injected wallets; an error once a standard wallet has
+    /// dropped its account.
+    fn standard_account(&self) -> Option<Result<(&StandardWallet, &JsValue), String>> {
+        let wallet = self.standard.as_ref()?;
+        Some(
+            self.account
+                .as_ref()
+                .map(|account| (wallet, account))
+                .ok_or_else(|| "Wallet not connected".to_string()),
+        )
+    }
+
+    /// Asks the wallet to end the session, then forgets it locally even if
+    /// the wallet refused.
     pub async fn disconnect(&mut self) -> Result<(), String> {
+        let result = match &self.standard {
+            Some(wallet) => wallet.disconnect().await,
+            None if self.connected => Self::disconnect_injected().await,
+            None => Ok(()),
+        };
         self.connected = false;
         self.public_key = None;
+        self.standard = None;
+        self.account = None;
+        result
+    }
+
+    async fn disconnect_injected() -> Result<(), String> {
+        let window = web_sys::window().ok_or("window not available")?;
+        let solana = js_sys::Reflect::get(&window, &JsValue::from_str("solana"))
+            .map_err(|_| "Wallet not found")?;
+        let Ok(disconnect_fn) = js_sys::Reflect::get(&solana, &JsValue::from_str("disconnect"))
+            .and_then(|f| f.dyn_into::<js_sys::Function>())
+        else {
+            return Ok(());
+        };
+        let result = disconnect_fn
+            .call0(&solana)
+            .map_err(|_| "disconnect failed")?;
+        if let Ok(promise) = result.dyn_into::<js_sys::Promise>() {
+            wasm_bindgen_futures::JsFuture::from(promise)
+                .await
+                .map_err(js_error_to_string)?;
+        }
         Ok(())
     }
 
@@ -121,6 +293,12 @@ impl WalletAdapter {
     }
 
     pub async fn sign_message_base64(&self, message_b64: &str) -> Result<String, String> {
+        if let Some(standard) = self.standard_account() {
+            let (wallet, account) = standard?;
+            let message = B64.decode(message_b64).map_err(|e| e.to_string())?;
+            let signature = wallet.sign_message(account, &message).await?;
+            return Ok(B64.encode(signature));
+        }
         let window = web_sys::window().ok_or("window not available")?;
         let td = js_sys::Reflect::get(&window, &JsValue::from_str("td"))
             .map_err(|_| "td helper not found")?;
@@ -219,6 +397,27 @@ impl WalletAdapter {
         Ok(())
     }
 
+    pub async fn scan_qr_image(&self, input_id: &str) -> Result<Vec<String>, String> {
+        let window = web_sys::window().ok_or("window not available")?;
+        let td = js_sys::Reflect::get(&window, &JsValue::from_str("td"))
+            .map_err(|_| "td helper not found")?;
+        let scan_fn = js_sys::Reflect::get(&td, &JsValue::from_str("scanQrImage"))
+            .map_err(|_| "scanQrImage not found")?;
+        let scan_fn: js_sys::Function = scan_fn
+            .dyn_into()
+            .map_err(|_| "scanQrImage is not a function")?;
+        let result = scan_fn
+            .call1(&td, &JsValue::from_str(input_id))
+            .map_err(|_| "scanQrImage failed")?;
+        let promise: js_sys::Promise = result
+            .dyn_into()
+            .map_err(|_| "scanQrImage didn't return promise")?;
+        let value = wasm_bindgen_futures::JsFuture::from(promise)
+            .await
+            .map_err(js_error_to_string)?;
+        from_value(value).map_err(|e| e.to_string())
+    }
+
     pub async fn sign_passkey(
         &self,
         challenge_b64: &str,
@@ -244,12 +443,47 @@ impl WalletAdapter {
         from_value(value).map_err(|e| e.to_string())
     }
 
+    /// Asks the wallet to sign a serialized (versioned) transaction and
+    /// returns the signed transaction, base64-encoded.
+    pub async fn sign_transaction_base64(&self, tx_b64: &str) -> Result<String, String> {
+        if let Some(standard) = self.standard_account() {
+            let (wallet, account) = standard?;
+            let tx = B64.decode(tx_b64).map_err(|e| e.to_string())?;
+            let chain = config::cluster().chain_id();
+            let signed = wallet.sign_transaction(account, chain, &tx).await?;
+            return Ok(B64.encode(signed));
+        }
+        let window = web_sys::window().ok_or("window not available")?;
+        let td = js_sys::Reflect::get(&window, &JsValue::from_str("td"))
+            .map_err(|_| "td helper not found")?;
+        let sign_fn = js_sys::Reflect::get(&td, &JsValue::from_str("signTransaction"))
+            .map_err(|_| "signTransaction not found")?;
+        let sign_fn: js_sys::Function = sign_fn
+            .dyn_into()
+            .map_err(|_| "signTransaction is not a function")?;
+        let result = sign_fn
+            .call1(&td, &JsValue::from_str(tx_b64))
+            .map_err(|_| "signTransaction failed")?;
+        let promise: js_sys::Promise = result
+            .dyn_into()
+            .map_err(|_| "signTransaction didn't return promise")?;
+        let signed = wasm_bindgen_futures::JsFuture::from(promise)
+            .await
+            .map_err(js_error_to_string)?;
+        signed.as_string().ok_or("signed transaction not a string".to_string())
+    }
+
     pub async fn send_instructions_json(
         &self,
         rpc_url: &str,
         fee_payer: &str,
         instructions_json: &str,
     ) -> Result<String, String> {
+        if self.standard.is_some() {
+            return self
+                .send_instructions_standard(rpc_url, fee_payer, instructions_json)
+                .await;
+        }
         let window = web_sys::window().ok_or("window not available")?;
         let td = js_sys::Reflect::get(&window, &JsValue::from_str("td"))
             .map_err(|_| "td helper not found")?;
@@ -277,12 +511,77 @@ impl WalletAdapter {
     }
 }
 
+impl WalletAdapter {
+    /// Same as the `td.sendInstructions` path, but compiled and sent from
+    /// Rust since standard wallets only sign serialized transactions.
+    async fn send_instructions_standard(
+        &self,
+        rpc_url: &str,
+        fee_payer: &str,
+        instructions_json: &str,
+    ) -> Result<String, String> {
+        let instructions: Vec<JsInstruction> =
+            serde_json::from_str(instructions_json).map_err(|e| e.to_string())?;
+        let instructions = instructions
+            .iter()
+            .map(instruction_from_js)
+            .collect::<Result<Vec<_>, _>>()?;
+        let payer = Pubkey::from_str(fee_payer).map_err(|e| e.to_string())?;
+        let tx = build_unsigned(rpc_url, &payer, &instructions, &[]).await?;
+        let signed = self.sign_transaction_base64(&tx.base64).await?;
+        let signature = send_transaction_base64(rpc_url, &signed).await?;
+        storage::add_pending_tx(&signature, config::cluster());
+        let confirmed = wait_for_confirmation(rpc_url, &signature, CONFIRM_TIMEOUT_MS).await;
+        // On timeout the entry stays so the next load can check it again.
+        if !matches!(&confirmed, Err(e) if e.starts_with("timed out")) {
+            storage::remove_pending_tx(&signature);
+        }
+        confirmed?;
+        Ok(signature)
+    }
+}
+
 impl Default for WalletAdapter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+fn watch_injected(listener: impl FnMut(Option<JsValue>) + 'static) -> Option<js_sys::Function> {
+    let window = web_sys::window()?;
+    let provider = js_sys::Reflect::get(&window, &JsValue::from_str("solana")).ok()?;
+    let on: js_sys::Function = js_sys::Reflect::get(&provider, &JsValue::from_str("on"))
+        .ok()?
+        .dyn_into()
+        .ok()?;
+
+    let listener = Rc::new(RefCell::new(listener));
+    let on_account = {
+        let listener = listener.clone();
+        Closure::<dyn FnMut(JsValue)>::new(move |public_key: JsValue| {
+            let account = (!public_key.is_null() && !public_key.is_undefined()).then_some(public_key);
+            (listener.borrow_mut())(account)
+        })
+    };
+    let on_disconnect = Closure::<dyn FnMut()>::new(move || (listener.borrow_mut())(None));
+    on.call2(&provider, &JsValue::from_str("accountChanged"), on_account.as_ref())
+        .ok()?;
+    on.call2(&provider, &JsValue::from_str("disconnect"), on_disconnect.as_ref())
+        .ok()?;
+
+    // The closures live until the returned function removes them.
+    let off = Closure::once_into_js(move || {
+        let remove = js_sys::Reflect::get(&provider, &JsValue::from_str("removeListener"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        if let Some(remove) = remove {
+            let _ = remove.call2(&provider, &JsValue::from_str("accountChanged"), on_account.as_ref());
+            let _ = remove.call2(&provider, &JsValue::from_str("disconnect"), on_disconnect.as_ref());
+        }
+    });
+    off.dyn_into().ok()
+}
+
 fn js_error_to_string(e: JsValue) -> String {
     if let Some(s) = e.as_string() {
         return s;
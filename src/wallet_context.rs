@@ -0,0 +1,141 @@
+// App-wide wallet connection. The adapter is the only record of which
+// account is connected; components read it through `use_wallet` and
+// re-render when the wallet switches accounts or disconnects. The SIWS
+// session lives alongside it and is dropped as soon as the account changes.
+use dioxus::prelude::*;
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+
+use crate::siws::{self, SiwsSession};
+use crate::storage;
+use crate::wallet::{WalletAdapter, WalletChoice};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct WalletContext {
+    pub adapter: Signal<WalletAdapter>,
+    pub session: Signal<Option<SiwsSession>>,
+    unsubscribe: Signal<Option<Function>>,
+}
+
+impl WalletContext {
+    pub fn address(&self) -> Option<String> {
+        self.adapter.read().get_public_key()
+    }
+
+    /// Connects `choice` and follows its account-change and disconnect events.
+    pub async fn connect(self, choice: WalletChoice) -> Result<String, String> {
+        self.connect_with(choice, false).await
+    }
+
+    /// Silently reconnects the wallet used last time, if it is still
+    /// installed and still trusts this site.
+    pub async fn reconnect_last(self) -> Result<Option<String>, String> {
+        let Some(name) = storage::LAST_WALLET.load() else {
+            return Ok(None);
+        };
+        let Some(choice) = WalletChoice::available().into_iter().find(|c| c.name() == name) else {
+            return Ok(None);
+        };
+        self.connect_with(choice, true).await.map(Some)
+    }
+
+    async fn connect_with(mut self, choice: WalletChoice, silent: bool) -> Result<String, String> {
+        let mut adapter = self.adapter.peek().clone();
+        let name = choice.name();
+        let address = if silent {
+            adapter.reconnect_choice(choice).await?
+        } else {
+            adapter.connect_choice(choice).await?
+        };
+        storage::LAST_WALLET.save(&name);
+        self.stop_watching();
+
+        let mut shared = self.adapter;
+        let off = adapter.watch_account(move |account| {
+            let address = shared.write().set_account(account);
+            match &address {
+                Some(address) => log::info!("Wallet switched to {}", address),
+                None => log::info!("Wallet disconnected"),
+            }
+            self.drop_session_unless(address.as_deref());
+        });
+        self.unsubscribe.set(off);
+        self.adapter.set(adapter);
+        self.drop_session_unless(Some(&address));
+        Ok(address)
+    }
+
+    pub async fn disconnect(mut self) -> Result<(), String> {
+        self.stop_watching();
+        let mut adapter = self.adapter.peek().clone();
+        let result = adapter.disconnect().await;
+        self.adapter.set(adapter);
+        self.sign_out();
+        storage::LAST_WALLET.clear();
+        result
+    }
+
+    /// Signs a SIWS message with the connected account and keeps the session.
+    pub async fn sign_in(mut self) -> Result<SiwsSession, String> {
+        let address = self.address().ok_or("Connect a wallet first")?;
+        let adapter = self.adapter.peek().clone();
+        let session = siws::sign_in(&adapter, &address).await?;
+        self.session.set(Some(session.clone()));
+        Ok(session)
+    }
+
+    pub fn sign_out(mut self) {
+        siws::clear_session();
+        self.session.set(None);
+    }
+
+    /// The session, if it belongs to the connected account and is unexpired.
+    pub fn active_session(&self) -> Option<SiwsSession> {
+        let address = self.address()?;
+        self.session
+            .read()
+            .clone()
+            .filter(|s| s.is_valid_for(&address, siws::now_secs()))
+    }
+
+    fn drop_session_unless(self, address: Option<&str>) {
+        let keep = match (self.session.peek().as_ref(), address) {
+            (Some(session), Some(address)) => session.address == address,
+            (None, _) => true,
+            _ => false,
+        };
+        if !keep {
+            self.sign_out();
+        }
+    }
+
+    fn stop_watching(&mut self) {
+        if let Some(off) = self.unsubscribe.write().take() {
+            let _ = off.call0(&JsValue::NULL);
+        }
+    }
+}
+
+/// Installs the shared wallet and reconnects the last one used; call once
+/// from the root component.
+pub fn use_wallet_provider() -> WalletContext {
+    let wallet = use_context_provider(|| WalletContext {
+        adapter: Signal::new(WalletAdapter::new()),
+        session: Signal::new(siws::load_session()),
+        unsubscribe: Signal::new(None),
+    });
+    use_hook(move || {
+        spawn(async move {
+            match wallet.reconnect_last().await {
+                Ok(Some(address)) => log::info!("Reconnected {}", address),
+                Ok(None) => {}
+                Err(e) => log::info!("Last wallet needs approval again: {}", e),
+            }
+        })
+    });
+    wallet
+}
+
+pub fn use_wallet() -> WalletContext {
+    use_context()
+}
@@ -0,0 +1,249 @@
+// Wallet Standard discovery. Wallets announce themselves with a
+// `wallet-standard:register-wallet` event; the app answers with
+// `wallet-standard:app-ready` so wallets that loaded first register too.
+// Only wallets that can connect and sign Solana transactions are kept.
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+pub const FEATURE_CONNECT: &str = "standard:connect";
+pub const FEATURE_DISCONNECT: &str = "standard:disconnect";
+pub const FEATURE_EVENTS: &str = "standard:events";
+pub const FEATURE_SIGN_TRANSACTION: &str = "solana:signTransaction";
+pub const FEATURE_SIGN_MESSAGE: &str = "solana:signMessage";
+
+const REGISTER_EVENT: &str = "wallet-standard:register-wallet";
+const APP_READY_EVENT: &str = "wallet-standard:app-ready";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StandardWallet {
+    pub name: String,
+    pub icon: Option<String>,
+    handle: JsValue,
+}
+
+thread_local! {
+    static WALLETS: RefCell<Vec<StandardWallet>> = const { RefCell::new(Vec::new()) };
+    static STARTED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn get(target: &JsValue, key: &str) -> Result<JsValue, String> {
+    Reflect::get(target, &JsValue::from_str(key)).map_err(|_| format!("{} not readable", key))
+}
+
+fn method(target: &JsValue, key: &str) -> Result<Function, String> {
+    get(target, key)?
+        .dyn_into()
+        .map_err(|_| format!("{} is not a function", key))
+}
+
+async fn resolve(value: JsValue) -> Result<JsValue, String> {
+    match value.dyn_into::<Promise>() {
+        Ok(promise) => JsFuture::from(promise).await.map_err(js_error_to_string),
+        Err(value) => Ok(value),
+    }
+}
+
+fn js_error_to_string(e: JsValue) -> String {
+    e.as_string()
+        .or_else(|| get(&e, "message").ok().and_then(|m| m.as_string()))
+        .unwrap_or_else(|| "request rejected".to_string())
+}
+
+pub fn account_address(account: &JsValue) -> Option<String> {
+    get(account, "address").ok()?.as_string()
+}
+
+impl StandardWallet {
+    fn from_handle(handle: JsValue) -> Option<Self> {
+        let name = get(&handle, "name").ok()?.as_string()?;
+        let icon = get(&handle, "icon").ok().and_then(|i| i.as_string());
+        let wallet = Self { name, icon, handle };
+        wallet.is_solana().then_some(wallet)
+    }
+
+    fn feature(&self, name: &str) -> Option<JsValue> {
+        let features = get(&self.handle, "features").ok()?;
+        let feature = get(&features, name).ok()?;
+        (!feature.is_undefined()).then_some(feature)
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.feature(feature).is_some()
+    }
+
+    fn is_solana(&self) -> bool {
+        let on_solana = get(&self.handle, "chains")
+            .ok()
+            .map(|chains| {
+                Array::from(&chains)
+                    .iter()
+                    .any(|c| c.as_string().is_some_and(|c| c.starts_with("solana:")))
+            })
+            .unwrap_or(false);
+        on_solana && self.supports(FEATURE_CONNECT) && self.supports(FEATURE_SIGN_TRANSACTION)
+    }
+
+    fn accounts(&self) -> Vec<JsValue> {
+        get(&self.handle, "accounts")
+            .map(|a| Array::from(&a).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Prompts the wallet and returns the first authorized account. With
+    /// `silent` the wallet must not prompt and only returns accounts the
+    /// user already authorized.
+    pub async fn connect(&self, silent: bool) -> Result<JsValue, String> {
+        let feature = self.feature(FEATURE_CONNECT).ok_or("wallet cannot connect")?;
+        let input = Object::new();
+        Reflect::set(&input, &"silent".into(), &silent.into()).map_err(js_error_to_string)?;
+        let output = resolve(
+            method(&feature, "connect")?
+                .call1(&feature, &input)
+                .map_err(js_error_to_string)?,
+        )
+        .await?;
+        get(&output, "accounts")
+            .ok()
+            .and_then(|a| Array::from(&a).iter().next())
+            .filter(|a| !a.is_undefined())
+            .or_else(|| self.accounts().into_iter().next())
+            .ok_or_else(|| format!("{} returned no accounts", self.name))
+    }
+
+    pub async fn disconnect(&self) -> Result<(), String> {
+        let Some(feature) = self.feature(FEATURE_DISCONNECT) else {
+            return Ok(());
+        };
+        resolve(
+            method(&feature, "disconnect")?
+                .call0(&feature)
+                .map_err(js_error_to_string)?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn sign_transaction(
+        &self,
+        account: &JsValue,
+        chain: &str,
+        transaction: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let feature = self
+            .feature(FEATURE_SIGN_TRANSACTION)
+            .ok_or("wallet cannot sign transactions")?;
+        let input = Object::new();
+        Reflect::set(&input, &"account".into(), account).map_err(js_error_to_string)?;
+        Reflect::set(&input, &"transaction".into(), &Uint8Array::from(transaction))
+            .map_err(js_error_to_string)?;
+        Reflect::set(&input, &"chain".into(), &chain.into()).map_err(js_error_to_string)?;
+        let outputs = resolve(
+            method(&feature, "signTransaction")?
+                .call1(&feature, &input)
+                .map_err(js_error_to_string)?,
+        )
+        .await?;
+        let signed = get(&Array::from(&outputs).get(0), "signedTransaction")?;
+        Ok(Uint8Array::new(&signed).to_vec())
+    }
+
+    /// Returns the 64-byte ed25519 signature over `message`.
+    pub async fn sign_message(&self, account: &JsValue, message: &[u8]) -> Result<Vec<u8>, String> {
+        let feature = self
+            .feature(FEATURE_SIGN_MESSAGE)
+            .ok_or("signMessage not supported by this wallet")?;
+        let input = Object::new();
+        Reflect::set(&input, &"account".into(), account).map_err(js_error_to_string)?;
+        Reflect::set(&input, &"message".into(), &Uint8Array::from(message))
+            .map_err(js_error_to_string)?;
+        let outputs = resolve(
+            method(&feature, "signMessage")?
+                .call1(&feature, &input)
+                .map_err(js_error_to_string)?,
+        )
+        .await?;
+        let signature = get(&Array::from(&outputs).get(0), "signature")?;
+        Ok(Uint8Array::new(&signature).to_vec())
+    }
+
+    /// Calls `listener` with the new first account whenever the wallet's
+    /// accounts change; `None` means the wallet dropped every account.
+    /// Returns the unsubscribe function.
+    pub fn on_accounts_changed(
+        &self,
+        mut listener: impl FnMut(Option<JsValue>) + 'static,
+    ) -> Option<Function> {
+        let feature = self.feature(FEATURE_EVENTS)?;
+        let on = method(&feature, "on").ok()?;
+        let callback = Closure::<dyn FnMut(JsValue)>::new(move |properties: JsValue| {
+            let Ok(accounts) = get(&properties, "accounts") else {
+                return;
+            };
+            if accounts.is_undefined() {
+                return;
+            }
+            listener(Array::from(&accounts).iter().next().filter(|a| !a.is_undefined()));
+        });
+        let off = on
+            .call2(&feature, &"change".into(), callback.as_ref())
+            .ok()?;
+        callback.forget();
+        off.dyn_into().ok()
+    }
+}
+
+fn register(handle: JsValue) {
+    let Some(wallet) = StandardWallet::from_handle(handle) else {
+        return;
+    };
+    log::info!("Wallet Standard: registered {}", wallet.name);
+    WALLETS.with(|w| {
+        let mut wallets = w.borrow_mut();
+        if !wallets.iter().any(|existing| existing.name == wallet.name) {
+            wallets.push(wallet);
+        }
+    });
+}
+
+/// Installs the register listener and announces the app. Safe to call more
+/// than once.
+pub fn start_discovery() {
+    if STARTED.with(|s| s.replace(true)) {
+        return;
+    }
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+
+    let register_fn = Closure::<dyn FnMut(JsValue) -> JsValue>::new(|handle: JsValue| {
+        register(handle);
+        // Unregistering is not supported; hand back a no-op.
+        Function::new_no_args("").into()
+    });
+    let api = Object::new();
+    let _ = Reflect::set(&api, &"register".into(), register_fn.as_ref());
+    register_fn.forget();
+
+    let api_for_listener = api.clone();
+    let on_register = Closure::<dyn FnMut(web_sys::CustomEvent)>::new(move |event: web_sys::CustomEvent| {
+        if let Ok(callback) = event.detail().dyn_into::<Function>() {
+            let _ = callback.call1(&JsValue::NULL, &api_for_listener);
+        }
+    });
+    let _ = window
+        .add_event_listener_with_callback(REGISTER_EVENT, on_register.as_ref().unchecked_ref());
+    on_register.forget();
+
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&api);
+    if let Ok(event) = web_sys::CustomEvent::new_with_event_init_dict(APP_READY_EVENT, &init) {
+        let _ = window.dispatch_event(&event);
+    }
+}
+
+pub fn wallets() -> Vec<StandardWallet> {
+    WALLETS.with(|w| w.borrow().clone())
+}